@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use checkmate::model::State;
+use checkmate::formats::{ToFEN, ToState};
+
+//  The starting position, a crowded middlegame with every move type available, and a
+//  sparse endgame.
+const POSITIONS: [(&str, &str); 3] = [
+    ("initial", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1")
+];
+
+fn states() -> Vec<(&'static str, State)> {
+    POSITIONS.iter().map(|(name, fen)| (*name, fen.to_fen().to_state().unwrap())).collect()
+}
+
+fn bench_get_legal_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_legal_moves");
+    for (name, state) in states() {
+        group.bench_function(name, |b| b.iter(|| black_box(&state).get_legal_moves()));
+    }
+    group.finish();
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft_3");
+    group.sample_size(10);
+    for (name, state) in states() {
+        group.bench_function(name, |b| b.iter(|| black_box(&state).perft(3)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_legal_moves, bench_perft);
+criterion_main!(benches);
@@ -0,0 +1,529 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::model::{State, Move};
+use super::agent::Agent;
+use super::eval::{Evaluator, TaperedEvaluator};
+use super::transposition::{TranspositionTable, Bound};
+use super::ordering::{MoveOrderer, same_move, is_quiet};
+
+/// Score of delivering mate on the next ply. Mates further away score less by one per
+/// ply, so scores within [`MAX_PLY`] of this are mates.
+pub const MATE_SCORE: i32 = 30_000;
+/// Deepest ply the search reaches, past which scores can't be mates.
+pub const MAX_PLY: u32 = 128;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+//  Nodes between checks of the clock, which is slower to read than a counter.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// How far [`AlphaBetaAgent`] searches. Search stops at whichever limit it reaches first,
+/// though the first iteration always completes so there is a move to play.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchLimits {
+    pub depth: u32,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>
+}
+
+impl SearchLimits {
+    /// Search to `depth` plies, with no node or time limit.
+    pub fn depth(depth: u32) -> Self {
+        Self{depth: depth.clamp(1, MAX_PLY), nodes: None, time: None}
+    }
+
+    pub fn with_nodes(mut self, nodes: u64) -> Self {
+        self.nodes = Some(nodes);
+
+        self
+    }
+
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+
+        self
+    }
+}
+
+/// The outcome of the deepest completed iteration.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Move,
+    /// In centipawns for the side to move, or within [`MAX_PLY`] of [`MATE_SCORE`].
+    pub score: i32,
+    pub depth: u32,
+    /// Nodes visited over every iteration, including any abandoned one.
+    pub nodes: u64,
+    /// The share of `nodes` visited by quiescence search.
+    pub quiescence_nodes: u64,
+    /// The line the search expects, starting with `best_move`.
+    pub principal_variation: Vec<Move>
+}
+
+/// `AlphaBetaAgent` searches with negamax and alpha-beta pruning, deepening one ply at a
+/// time until a [`SearchLimits`] limit is reached and playing the first move of the
+/// principal variation. Positions are scored by `E`, [`TaperedEvaluator`] unless given.
+///
+/// Results are kept in a [`TranspositionTable`] between searches, and moves are ordered
+/// by a [`MoveOrderer`], so an agent playing a game reuses what it learnt on earlier moves.
+#[derive(Clone)]
+pub struct AlphaBetaAgent<E: Evaluator = TaperedEvaluator> {
+    limits: SearchLimits,
+    evaluator: E,
+    table: TranspositionTable,
+    ordering: MoveOrderer
+}
+
+#[async_trait]
+impl<E: Evaluator> Agent for AlphaBetaAgent<E> {
+    async fn get_move_for_model(&mut self, state: &State) -> Move {
+        self.search(state).unwrap().best_move
+    }
+}
+
+impl Default for AlphaBetaAgent {
+    fn default() -> Self {
+        Self::new(SearchLimits::depth(4))
+    }
+}
+
+impl AlphaBetaAgent {
+    pub fn new(limits: SearchLimits) -> Self {
+        Self::with_evaluator(limits, TaperedEvaluator)
+    }
+}
+
+impl<E: Evaluator> AlphaBetaAgent<E> {
+    pub fn with_evaluator(limits: SearchLimits, evaluator: E) -> Self {
+        Self{limits, evaluator, table: TranspositionTable::default(), ordering: MoveOrderer::default()}
+    }
+
+    pub fn with_ordering(mut self, ordering: MoveOrderer) -> Self {
+        self.ordering = ordering;
+
+        self
+    }
+
+    pub fn with_table(mut self, table: TranspositionTable) -> Self {
+        self.table = table;
+
+        self
+    }
+
+    pub fn table(&self) -> &TranspositionTable {
+        &self.table
+    }
+
+    /// Return the table mutably, for example to clear it before a new game.
+    pub fn table_mut(&mut self) -> &mut TranspositionTable {
+        &mut self.table
+    }
+
+    pub fn ordering_mut(&mut self) -> &mut MoveOrderer {
+        &mut self.ordering
+    }
+
+    /// Search `state`, returning `None` if the side to move has no legal moves.
+    pub fn search(&mut self, state: &State) -> Option<SearchResult> {
+        self.table.new_search();
+        self.ordering.new_search();
+
+        let mut search = Search{
+            limits: self.limits,
+            evaluator: &self.evaluator,
+            table: &mut self.table,
+            ordering: &mut self.ordering,
+            started: Instant::now(),
+            nodes: 0,
+            quiescence_nodes: 0,
+            enforce_limits: false,
+            aborted: false,
+            previous_pv: Vec::new()
+        };
+        let mut state = state.clone();
+        let mut result: Option<SearchResult> = None;
+
+        //  Limits built as a literal skip SearchLimits::depth's clamp.
+        for depth in 1..=self.limits.depth.clamp(1, MAX_PLY) {
+            search.enforce_limits = depth > 1;
+
+            let mut pv = Vec::new();
+            let score = search.negamax(&mut state, depth, 0, -INFINITY, INFINITY, &mut pv);
+            if search.aborted || pv.is_empty() {
+                break;
+            }
+
+            search.previous_pv = pv.clone();
+            result = Some(SearchResult{
+                best_move: pv[0].clone(), score, depth,
+                nodes: search.nodes, quiescence_nodes: search.quiescence_nodes,
+                principal_variation: pv
+            });
+
+            //  Deeper iterations can't find a shorter mate.
+            if score.abs() > MATE_SCORE - MAX_PLY as i32 {
+                break;
+            }
+        }
+
+        result.map(|found| SearchResult{nodes: search.nodes, quiescence_nodes: search.quiescence_nodes, ..found})
+    }
+}
+
+struct Search<'a, E: Evaluator> {
+    limits: SearchLimits,
+    evaluator: &'a E,
+    table: &'a mut TranspositionTable,
+    ordering: &'a mut MoveOrderer,
+    started: Instant,
+    nodes: u64,
+    quiescence_nodes: u64,
+    enforce_limits: bool,
+    aborted: bool,
+    //  The principal variation of the last completed iteration, searched first.
+    previous_pv: Vec<Move>
+}
+
+impl<E: Evaluator> Search<'_, E> {
+    fn should_abort(&mut self) -> bool {
+        if !self.enforce_limits || self.aborted {
+            return self.aborted;
+        }
+
+        let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+        let out_of_time = self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) &&
+            self.limits.time.is_some_and(|time| self.started.elapsed() >= time);
+        self.aborted = out_of_nodes || out_of_time;
+
+        self.aborted
+    }
+
+    /// Return the score of `state` for the side to move, searched `depth` plies deeper, and
+    /// fill `pv` with the line leading to it. Scores outside `alpha..beta` are bounds.
+    fn negamax(
+        &mut self, state: &mut State, depth: u32, ply: u32,
+        mut alpha: i32, beta: i32, pv: &mut Vec<Move>
+    ) -> i32 {
+        pv.clear();
+        //  Leaves search on through captures, so they aren't scored mid-exchange.
+        if depth == 0 {
+            return self.quiesce(state, ply, alpha, beta);
+        }
+        if self.should_abort() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0 && is_draw(state) {
+            return 0;
+        }
+
+        let key = state.key();
+        let mut hash_move = None;
+        if let Some(entry) = self.table.probe(key, ply) {
+            //  The root always searches, so there is a move to play.
+            if ply > 0 && entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha
+                };
+
+                if cutoff {
+                    if entry.bound == Bound::Exact && entry.score > alpha && entry.score < beta {
+                        self.table_line(state, depth, pv);
+                    }
+
+                    return entry.score;
+                }
+            }
+
+            hash_move = entry.best_move;
+        }
+
+        let mut moves = state.get_legal_moves();
+        if moves.is_empty() {
+            return match state.is_check_against(state.active_color) {
+                true => -MATE_SCORE + ply as i32,
+                false => 0
+            };
+        }
+        if ply >= MAX_PLY {
+            return self.evaluator.evaluate(state);
+        }
+
+        //  The stored best move, or else the last iteration's best move at this ply, is
+        //  likely good here too.
+        let first_move = hash_move.or_else(|| self.previous_pv.get(ply as usize).cloned());
+        self.ordering.order(state, &mut moves, first_move.as_ref(), ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut child_pv = Vec::new();
+        let mut quiets_tried = Vec::new();
+        for check_move in &moves {
+            let undo = state.make_move(check_move);
+            let score = -self.negamax(state, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            state.unmake_move(undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            if score > alpha {
+                alpha = score;
+
+                pv.clear();
+                pv.push(check_move.clone());
+                pv.append(&mut child_pv);
+
+                if alpha >= beta {
+                    self.ordering.record_cutoff(state, check_move, ply, depth, &quiets_tried);
+                    break;
+                }
+            }
+
+            if is_quiet(check_move) {
+                quiets_tried.push(check_move.clone());
+            }
+        }
+
+        let bound = match best_score {
+            score if score >= beta => Bound::Lower,
+            score if score > original_alpha => Bound::Exact,
+            _ => Bound::Upper
+        };
+        self.table.store(key, depth, bound, best_score, pv.first().cloned(), ply);
+
+        best_score
+    }
+
+    /// Return the score of `state` for the side to move once captures and promotions have
+    /// played out. The side to move may stand on the static evaluation instead, unless in
+    /// check, where every evasion is searched.
+    fn quiesce(&mut self, state: &mut State, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_abort() {
+            return 0;
+        }
+        self.nodes += 1;
+        self.quiescence_nodes += 1;
+
+        if is_draw(state) {
+            return 0;
+        }
+
+        let in_check = state.is_check_against(state.active_color);
+        let moves = state.get_legal_moves();
+        if moves.is_empty() {
+            return match in_check {
+                true => -MATE_SCORE + ply as i32,
+                false => 0
+            };
+        }
+        if ply >= MAX_PLY {
+            return self.evaluator.evaluate(state);
+        }
+
+        let mut best_score = -INFINITY;
+        let mut moves: Vec<Move> = match in_check {
+            true => moves,
+            false => {
+                best_score = self.evaluator.evaluate(state);
+                if best_score >= beta {
+                    return best_score;
+                }
+                alpha = alpha.max(best_score);
+
+                //  Captures that lose material can't raise the score above standing pat.
+                moves.into_iter()
+                    .filter(|m| !is_quiet(m) && state.see(m) >= 0)
+                    .collect()
+            }
+        };
+        self.ordering.order(state, &mut moves, None, ply);
+
+        for check_move in &moves {
+            let undo = state.make_move(check_move);
+            let score = -self.quiesce(state, ply + 1, -beta, -alpha);
+            state.unmake_move(undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+
+    /// Fill `pv` with up to `depth` moves by following stored best moves from `state`,
+    /// for lines cut short by an exact table hit.
+    fn table_line(&self, state: &mut State, depth: u32, pv: &mut Vec<Move>) {
+        let mut undos = Vec::new();
+
+        while (pv.len() as u32) < depth {
+            let stored = match self.table.get(state.key(), 0).and_then(|entry| entry.best_move) {
+                Some(stored) => stored,
+                None => break
+            };
+            let legal = match state.get_legal_moves().into_iter().find(|m| same_move(m, &stored)) {
+                Some(legal) => legal,
+                None => break
+            };
+
+            undos.push(state.make_move(&legal));
+            pv.push(legal);
+        }
+
+        while let Some(undo) = undos.pop() {
+            state.unmake_move(undo);
+        }
+    }
+}
+
+//  Repeating a position is scored as a draw, since either side could repeat again.
+fn is_draw(state: &State) -> bool {
+    state.halfmove_clock >= 100 || state.repetitions() > 1 || state.is_insufficient_materiel()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{ToFEN, ToState, ToUci};
+    use crate::agents::{MaterialEvaluator, Heuristics};
+
+    fn search(fen: &str, limits: SearchLimits) -> SearchResult {
+        AlphaBetaAgent::new(limits).search(&fen.to_fen().to_state().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", SearchLimits::depth(3));
+
+        assert_eq!(result.best_move.to_uci().to_string(), "a1a8");
+        assert_eq!(result.score, MATE_SCORE - 1);
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        //  Both rooks are needed: the first cuts the king off, the second mates.
+        let result = search("7k/8/8/8/8/8/8/RR4K1 w - - 0 1", SearchLimits::depth(5));
+
+        assert_eq!(result.score, MATE_SCORE - 3);
+        assert_eq!(result.principal_variation.len(), 3);
+    }
+
+    #[test]
+    fn test_wins_material() {
+        //  The knight on d5 is free, and the queen shouldn't be traded for it.
+        let result = search("4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1", SearchLimits::depth(3));
+
+        assert_eq!(result.best_move.to_uci().to_string(), "d2d5");
+        assert!(result.score >= 900);
+    }
+
+    #[test]
+    fn test_principal_variation_is_legal() {
+        let state = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3".to_fen().to_state().unwrap();
+        let result = AlphaBetaAgent::new(SearchLimits::depth(4)).search(&state).unwrap();
+
+        let mut line_state = state.clone();
+        for line_move in &result.principal_variation {
+            let legal = line_state.get_legal_moves();
+            assert!(legal.iter().any(|m| same_move(m, line_move)));
+
+            line_state.make_move(line_move);
+        }
+        assert_eq!(result.principal_variation.len(), 4);
+    }
+
+    #[test]
+    fn test_limits() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+
+        let by_nodes = search(fen, SearchLimits::depth(20).with_nodes(5_000));
+        assert!(by_nodes.depth < 20);
+        assert!(by_nodes.nodes <= 5_000);
+
+        let started = Instant::now();
+        let by_time = search(fen, SearchLimits::depth(20).with_time(Duration::from_millis(100)));
+        assert!(by_time.depth < 20);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        assert!(AlphaBetaAgent::default().search(&"7k/5QQ1/8/8/8/8/8/K7 b - - 0 1".to_fen().to_state().unwrap()).is_none());
+
+        let unclamped = search(fen, SearchLimits{depth: 0, nodes: None, time: None});
+        assert_eq!(unclamped.depth, 1);
+    }
+
+    #[test]
+    fn test_evaluators() {
+        //  Material alone can't tell the quiet moves apart, but either evaluator wins the knight.
+        let state = "4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        let material = AlphaBetaAgent::with_evaluator(SearchLimits::depth(2), MaterialEvaluator).search(&state).unwrap();
+        let tapered = AlphaBetaAgent::new(SearchLimits::depth(2)).search(&state).unwrap();
+
+        assert_eq!(material.best_move.to_uci().to_string(), "d2d5");
+        assert_eq!(material.score, 900);
+        assert_eq!(tapered.best_move.to_uci().to_string(), "d2d5");
+    }
+
+    #[test]
+    fn test_table_is_shared() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let state = fen.to_fen().to_state().unwrap();
+        let mut agent = AlphaBetaAgent::new(SearchLimits::depth(4)).with_table(TranspositionTable::with_entries(1 << 16));
+
+        let first = agent.search(&state).unwrap();
+        let first_stats = agent.table().stats();
+        assert!(first_stats.hits > 0);
+        assert!(first_stats.stores > 0);
+
+        //  Searching again starts from what the first search stored.
+        let second = agent.search(&state).unwrap();
+        assert!(second.nodes < first.nodes);
+        assert!(agent.table().stats().hit_rate() > first_stats.hit_rate());
+        assert_eq!(second.score, first.score);
+
+        agent.table_mut().clear();
+        assert_eq!(agent.table().stats().probes, 0);
+    }
+
+    #[test]
+    fn test_quiescence() {
+        //  A one ply search without quiescence would take the pawn and lose the queen.
+        let state = "4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        let result = AlphaBetaAgent::with_evaluator(SearchLimits::depth(1), MaterialEvaluator).search(&state).unwrap();
+
+        assert_ne!(result.best_move.to_uci().to_string(), "d2d5");
+        assert_eq!(result.score, 700);
+
+        //  Trading into a recapture is fine when it wins material overall.
+        let state = "4k3/8/2p5/3r4/8/4N3/8/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        let result = AlphaBetaAgent::with_evaluator(SearchLimits::depth(1), MaterialEvaluator).search(&state).unwrap();
+
+        assert_eq!(result.best_move.to_uci().to_string(), "e3d5");
+        assert_eq!(result.score, -100);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let state = "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 1".to_fen().to_state().unwrap();
+
+        let ordered = AlphaBetaAgent::new(SearchLimits::depth(4)).search(&state).unwrap();
+        let unordered = AlphaBetaAgent::new(SearchLimits::depth(4))
+            .with_ordering(MoveOrderer::new(Heuristics::none()))
+            .search(&state).unwrap();
+
+        assert!(ordered.nodes < unordered.nodes);
+        assert!(ordered.quiescence_nodes > 0 && ordered.quiescence_nodes < ordered.nodes);
+    }
+}
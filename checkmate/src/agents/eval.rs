@@ -0,0 +1,440 @@
+use std::ops;
+
+use crate::model::{State, Board, Bitboard, Color, PieceType, Position};
+
+/// `Evaluator` scores positions for search agents, so evaluation can change without
+/// touching search.
+pub trait Evaluator: Send {
+    /// Return the score of `state` in centipawns from the side to move's point of view.
+    fn evaluate(&self, state: &State) -> i32;
+}
+
+/// `MaterialEvaluator` counts [`PieceType::materiel_value`] and nothing else.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaterialEvaluator;
+
+impl Evaluator for MaterialEvaluator {
+    fn evaluate(&self, state: &State) -> i32 {
+        let mut score = 0;
+
+        for position in state.board.occupied() {
+            let piece = state.board[&position].as_ref().unwrap();
+            let value = piece.piece_type.materiel_value() as i32 * 100;
+
+            score += match piece.color == state.active_color {
+                true => value,
+                false => -value
+            };
+        }
+
+        score
+    }
+}
+
+/// `TaperedEvaluator` scores material, piece-square tables, mobility, king safety and pawn
+/// structure separately for the middlegame and endgame, then blends the two by how much
+/// material is left.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TaperedEvaluator;
+
+impl Evaluator for TaperedEvaluator {
+    fn evaluate(&self, state: &State) -> i32 {
+        let board = &state.board;
+        let total = side_score(board, Color::White) - side_score(board, Color::Black);
+
+        let phase = game_phase(board);
+        let white_score = (total.mg * phase + total.eg * (MAX_PHASE - phase)) / MAX_PHASE;
+
+        match state.active_color {
+            Color::White => white_score,
+            Color::Black => -white_score
+        }
+    }
+}
+
+/// A middlegame and an endgame score.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Score {
+    mg: i32,
+    eg: i32
+}
+
+impl Score {
+    const fn new(mg: i32, eg: i32) -> Self {
+        Self{mg, eg}
+    }
+}
+
+impl ops::Add for Score {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl ops::AddAssign for Score {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl ops::Sub for Score {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl ops::Mul<i32> for Score {
+    type Output = Self;
+
+    fn mul(self, factor: i32) -> Self {
+        Score::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+//  The phase counts minor pieces 1, rooks 2 and queens 4, so the starting position is
+//  all middlegame and bare kings and pawns all endgame.
+const MAX_PHASE: i32 = 24;
+
+const BISHOP_PAIR: Score = Score::new(30, 50);
+const DOUBLED_PAWN: Score = Score::new(-10, -20);
+const ISOLATED_PAWN: Score = Score::new(-10, -15);
+//  By rank from the pawn's own side, so index 6 is one step from promoting.
+const PASSED_PAWN: [Score; 8] = [
+    Score::new(0, 0), Score::new(5, 10), Score::new(10, 20), Score::new(15, 35),
+    Score::new(25, 55), Score::new(40, 85), Score::new(60, 120), Score::new(0, 0)
+];
+const PAWN_SHIELD: i32 = 12;
+
+fn material(piece_type: PieceType) -> Score {
+    match piece_type {
+        PieceType::Pawn => Score::new(82, 94),
+        PieceType::Knight => Score::new(337, 281),
+        PieceType::Bishop => Score::new(365, 297),
+        PieceType::Rook => Score::new(477, 512),
+        PieceType::Queen => Score::new(1025, 936),
+        PieceType::King => Score::new(0, 0)
+    }
+}
+
+fn phase_weight(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Knight | PieceType::Bishop => 1,
+        PieceType::Rook => 2,
+        PieceType::Queen => 4,
+        PieceType::Pawn | PieceType::King => 0
+    }
+}
+
+/// Return the mobility weight and the number of squares a piece of `piece_type` is
+/// expected to reach, for pieces that are scored on mobility.
+fn mobility_weight(piece_type: PieceType) -> Option<(Score, i32)> {
+    match piece_type {
+        PieceType::Knight => Some((Score::new(4, 4), 4)),
+        PieceType::Bishop => Some((Score::new(5, 5), 6)),
+        PieceType::Rook => Some((Score::new(2, 4), 7)),
+        PieceType::Queen => Some((Score::new(1, 2), 13)),
+        PieceType::Pawn | PieceType::King => None
+    }
+}
+
+/// Return the king safety weight of each attack on squares around the enemy king.
+fn attack_units(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Knight | PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 5,
+        PieceType::Pawn | PieceType::King => 0
+    }
+}
+
+//  Piece-square tables, laid out as the board is seen by white so the first row is the
+//  eighth rank. Black reads them mirrored.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20
+];
+
+//  The king hides behind its pawns in the middlegame and heads for the centre in the
+//  endgame.
+#[rustfmt::skip]
+const KING_MG_TABLE: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20
+];
+
+#[rustfmt::skip]
+const KING_EG_TABLE: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50
+];
+
+fn square_score(piece_type: PieceType, color: Color, position: &Position) -> Score {
+    let rank = match color {
+        Color::White => 7 - position.rank,
+        Color::Black => position.rank
+    };
+    let index = rank * 8 + position.file;
+
+    match piece_type {
+        PieceType::Pawn => Score::new(PAWN_TABLE[index], PAWN_TABLE[index]),
+        PieceType::Knight => Score::new(KNIGHT_TABLE[index], KNIGHT_TABLE[index]),
+        PieceType::Bishop => Score::new(BISHOP_TABLE[index], BISHOP_TABLE[index]),
+        PieceType::Rook => Score::new(ROOK_TABLE[index], ROOK_TABLE[index]),
+        PieceType::Queen => Score::new(QUEEN_TABLE[index], QUEEN_TABLE[index]),
+        PieceType::King => Score::new(KING_MG_TABLE[index], KING_EG_TABLE[index])
+    }
+}
+
+fn game_phase(board: &Board) -> i32 {
+    let phase: i32 = [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen].into_iter().map(|piece_type| {
+        let count = board.positions_of(Color::White, piece_type).len() + board.positions_of(Color::Black, piece_type).len();
+
+        count as i32 * phase_weight(piece_type)
+    }).sum();
+
+    phase.min(MAX_PHASE)
+}
+
+fn file_mask(file: usize) -> Bitboard {
+    Bitboard(0x0101010101010101 << file)
+}
+
+fn adjacent_files_mask(file: usize) -> Bitboard {
+    let mut mask = Bitboard::EMPTY;
+    if file > 0 {
+        mask |= file_mask(file - 1);
+    }
+    if file < 7 {
+        mask |= file_mask(file + 1);
+    }
+
+    mask
+}
+
+/// Return every square on a rank in front of `rank` from `color`'s side.
+fn ahead_mask(color: Color, rank: usize) -> Bitboard {
+    match color {
+        Color::White if rank < 7 => Bitboard(u64::MAX << ((rank + 1) * 8)),
+        Color::Black if rank > 0 => Bitboard(u64::MAX >> ((8 - rank) * 8)),
+        _ => Bitboard::EMPTY
+    }
+}
+
+/// Return everything but the king safety and pawn terms for `color`, which sum over its
+/// pieces.
+fn pieces_score(board: &Board, color: Color) -> Score {
+    let own = board.occupied_by(color);
+    let enemy_pawn_attacks = board.positions_of(!color, PieceType::Pawn).into_iter()
+        .fold(Bitboard::EMPTY, |attacks, position| attacks | board.attacks_from(&position));
+
+    let mut score = Score::default();
+    for position in own {
+        let piece_type = board[&position].as_ref().unwrap().piece_type;
+        score += material(piece_type) + square_score(piece_type, color, &position);
+
+        if let Some((weight, expected)) = mobility_weight(piece_type) {
+            let reachable = board.attacks_from(&position) & !own & !enemy_pawn_attacks;
+            score += weight * (reachable.len() as i32 - expected);
+        }
+    }
+
+    if board.positions_of(color, PieceType::Bishop).len() >= 2 {
+        score += BISHOP_PAIR;
+    }
+
+    score
+}
+
+/// Return the middlegame bonus for pawns sheltering `color`'s king, less a penalty that
+/// grows with the square of the enemy's attacks on the squares around it.
+fn king_safety(board: &Board, color: Color) -> Score {
+    let king_position = match board.king_position(color) {
+        Some(position) => position,
+        None => return Score::default()
+    };
+
+    let shield_ranks = ahead_mask(color, king_position.rank) & !ahead_mask(color, (king_position.rank as i32 + match color {
+        Color::White => 2,
+        Color::Black => -2
+    }).clamp(0, 7) as usize);
+    let shield_files = file_mask(king_position.file) | adjacent_files_mask(king_position.file);
+    let shield = (board.positions_of(color, PieceType::Pawn) & shield_ranks & shield_files).len() as i32;
+
+    let zone = board.attacks_from(&king_position) | Bitboard::from_position(&king_position);
+    let mut units = 0;
+    for position in board.occupied_by(!color) {
+        let piece_type = board[&position].as_ref().unwrap().piece_type;
+        units += attack_units(piece_type) * (board.attacks_from(&position) & zone).len() as i32;
+    }
+
+    Score::new(shield * PAWN_SHIELD - units * units / 4, 0)
+}
+
+fn pawn_structure(board: &Board, color: Color) -> Score {
+    let pawns = board.positions_of(color, PieceType::Pawn);
+    let enemy_pawns = board.positions_of(!color, PieceType::Pawn);
+
+    let mut score = Score::default();
+    for file in 0..8 {
+        let on_file = (pawns & file_mask(file)).len() as i32;
+        if on_file > 1 {
+            score += DOUBLED_PAWN * (on_file - 1);
+        }
+        if on_file > 0 && (pawns & adjacent_files_mask(file)).is_empty() {
+            score += ISOLATED_PAWN * on_file;
+        }
+    }
+
+    for position in pawns {
+        let blockers = ahead_mask(color, position.rank) & (file_mask(position.file) | adjacent_files_mask(position.file));
+        if (enemy_pawns & blockers).is_empty() {
+            let relative_rank = match color {
+                Color::White => position.rank,
+                Color::Black => 7 - position.rank
+            };
+
+            score += PASSED_PAWN[relative_rank];
+        }
+    }
+
+    score
+}
+
+fn side_score(board: &Board, color: Color) -> Score {
+    pieces_score(board, color) + king_safety(board, color) + pawn_structure(board, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{ToFEN, ToState};
+
+    fn evaluate(fen: &str) -> i32 {
+        TaperedEvaluator.evaluate(&fen.to_fen().to_state().unwrap())
+    }
+
+    //  Flip the board top to bottom and swap colors, which should score the same for the
+    //  side to move.
+    fn mirrored(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let placement: Vec<String> = fields[0].split('/').rev().map(|rank| {
+            rank.chars().map(|c| match c.is_uppercase() {
+                true => c.to_ascii_lowercase(),
+                false => c.to_ascii_uppercase()
+            }).collect()
+        }).collect();
+        let active = if fields[1] == "w" { "b" } else { "w" };
+
+        format!("{} {} - - 0 1", placement.join("/"), active)
+    }
+
+    #[test]
+    fn test_symmetry() {
+        assert_eq!(evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"), 0);
+
+        let fens = [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w - - 0 1",
+            "8/5k2/3p4/1p1Pp2p/pP2Pp1P/P4P1K/8/8 b - - 0 1",
+            "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w - - 0 1"
+        ];
+        for fen in fens {
+            assert_eq!(evaluate(fen), evaluate(&mirrored(fen)), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_material() {
+        assert!(evaluate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1") > 800);
+        assert!(evaluate("4k3/8/8/8/8/8/8/3QK3 b - - 0 1") < -800);
+        assert_eq!(MaterialEvaluator.evaluate(&"4k3/8/8/8/8/8/8/3RK3 b - - 0 1".to_fen().to_state().unwrap()), -500);
+    }
+
+    #[test]
+    fn test_terms() {
+        //  A centralised knight beats one on the rim.
+        assert!(evaluate("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1") > evaluate("4k3/8/8/8/N7/8/8/4K3 w - - 0 1"));
+
+        //  A passed pawn beats one the enemy pawn can stop.
+        assert!(evaluate("4k3/7p/8/1P6/8/8/8/4K3 w - - 0 1") > evaluate("4k3/2p5/8/1P6/8/8/8/4K3 w - - 0 1"));
+
+        //  Doubled and isolated pawns are weaker than connected ones.
+        assert!(evaluate("4k3/8/8/8/8/8/2PP4/4K3 w - - 0 1") > evaluate("4k3/8/8/8/8/2P5/2P5/4K3 w - - 0 1"));
+
+        //  The king belongs behind its pawns with queens on, and in the centre without.
+        let castled = "r1bq1rk1/ppp2ppp/2n2n2/8/8/2N2N2/PPP2PPP/R1BQ1RK1 w - - 0 1";
+        let exposed = "r1bq1rk1/ppp2ppp/2n2n2/8/8/2N1KN2/PPP2PPP/R1BQ1R2 w - - 0 1";
+        assert!(evaluate(castled) > evaluate(exposed));
+        assert!(evaluate("8/5k2/8/8/8/4K3/5P2/8 w - - 0 1") > evaluate("8/5k2/8/8/8/8/5P2/7K w - - 0 1"));
+    }
+}
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::model::{PieceType, State, Move};
+use crate::model::{State, Move};
 use super::agent::Agent;
 
 #[derive(Clone)]
@@ -9,20 +9,20 @@ pub struct HeurNoBlunderAgent;
 #[async_trait]
 impl Agent for HeurNoBlunderAgent {
     async fn get_move_for_model(&mut self, state: &State) -> Move {
-        let mut loss_min = PieceType::Queen.materiel_value() + 1;
+        let mut loss_min = i32::MAX;
         let mut best_play: Option<&Move> = None;
 
+        let mut next_state = state.clone();
         let moves = &state.get_legal_moves()[..];
         for check_move in moves {
-            let next_state = state.next_for_move(check_move);
-
-            let mut loss_max: u8 = 0;
-            let moves_against = next_state.get_legal_moves();
-            for attack_check in moves_against {
-                if let Some(piece) = &attack_check.taken {
-                    loss_max = piece.piece_type.materiel_value();
-                }
-            }
+            let undo = next_state.make_move(check_move);
+
+            //  A piece only hangs if the opponent comes out ahead after the exchange.
+            let loss_max = next_state.get_legal_moves().iter()
+                .filter(|reply| reply.taken.is_some())
+                .map(|reply| next_state.see(reply))
+                .fold(0, i32::max);
+            next_state.unmake_move(undo);
 
             if loss_max < loss_min {
                 loss_min = loss_max;
@@ -34,6 +34,12 @@ impl Agent for HeurNoBlunderAgent {
     }
 }
 
+impl Default for HeurNoBlunderAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HeurNoBlunderAgent {
     pub fn new() -> Self {
         Self{}
@@ -19,6 +19,12 @@ impl Agent for HeurRandAgent {
     }
 }
 
+impl Default for HeurRandAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HeurRandAgent {
     pub fn new() -> Self {
         Self{}
@@ -1,7 +1,15 @@
 mod agent;
 mod heur_rand;
 mod heur_no_blunder;
+mod eval;
+mod transposition;
+mod ordering;
+mod alpha_beta;
 
 pub use agent::Agent;
 pub use heur_rand::HeurRandAgent;
 pub use heur_no_blunder::HeurNoBlunderAgent;
+pub use eval::{Evaluator, MaterialEvaluator, TaperedEvaluator};
+pub use transposition::{TranspositionTable, TableEntry, TableStats, Bound};
+pub use ordering::{MoveOrderer, Heuristics};
+pub use alpha_beta::{AlphaBetaAgent, SearchLimits, SearchResult, MATE_SCORE, MAX_PLY};
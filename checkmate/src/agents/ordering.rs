@@ -0,0 +1,251 @@
+use std::cmp::Reverse;
+
+use bitmask_enum::bitmask;
+
+use crate::model::{State, Move, Piece};
+use super::alpha_beta::MAX_PLY;
+
+/// The move ordering heuristics a [`MoveOrderer`] applies, so each can be switched off to
+/// measure what it saves.
+#[bitmask(u8)]
+#[derive(Clone, Copy)]
+pub enum Heuristics {
+    /// The best move stored for the position, or from the last iteration's line.
+    HashMove,
+    /// Captures and promotions, most valuable victim first and least valuable attacker
+    /// breaking ties.
+    Captures,
+    /// Quiet moves that caused a cutoff at the same ply.
+    Killers,
+    /// Quiet moves by how often they caused cutoffs anywhere.
+    History,
+    /// The quiet move that last refuted the opponent's previous move.
+    Countermoves
+}
+
+//  Each heuristic outranks those after it, and history scores stay below them all.
+const HASH_MOVE_SCORE: i32 = 1 << 30;
+const CAPTURE_SCORE: i32 = 1 << 20;
+const KILLER_SCORE: i32 = 1 << 19;
+const COUNTERMOVE_SCORE: i32 = 1 << 18;
+const HISTORY_MAX: i32 = 1 << 16;
+
+/// `MoveOrderer` sorts moves so the likeliest to cause a cutoff are searched first, and
+/// learns from the cutoffs search reports back.
+#[derive(Clone)]
+pub struct MoveOrderer {
+    heuristics: Heuristics,
+    killers: Vec<[Option<Move>; 2]>,
+    //  By color, origin and destination square.
+    history: Vec<i32>,
+    //  By the previous move's piece and destination square.
+    countermoves: Vec<Option<Move>>
+}
+
+impl Default for MoveOrderer {
+    fn default() -> Self {
+        Self::new(Heuristics::all())
+    }
+}
+
+impl MoveOrderer {
+    pub fn new(heuristics: Heuristics) -> Self {
+        Self{
+            heuristics,
+            killers: vec![[None, None]; MAX_PLY as usize + 1],
+            history: vec![0; 2 * 64 * 64],
+            countermoves: vec![None; 12 * 64]
+        }
+    }
+
+    pub fn heuristics(&self) -> Heuristics {
+        self.heuristics
+    }
+
+    /// Forget everything learnt, as before a new game.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.heuristics);
+    }
+
+    /// Forget killers, which belong to the last search's plies, and halve history scores so
+    /// recent cutoffs count for more.
+    pub fn new_search(&mut self) {
+        self.killers.fill([None, None]);
+        self.history.iter_mut().for_each(|score| *score /= 2);
+    }
+
+    /// Sort `moves` from `state` at `ply` best first, with `hash_move` ahead of the rest.
+    /// Moves no heuristic ranks keep their order.
+    pub fn order(&self, state: &State, moves: &mut [Move], hash_move: Option<&Move>, ply: u32) {
+        let countermove = state.move_history.last()
+            .and_then(|previous| self.countermoves[countermove_index(&previous.piece, previous.to.index())].as_ref());
+
+        moves.sort_by_cached_key(|m| Reverse(self.score(m, hash_move, countermove, ply)));
+    }
+
+    fn score(&self, scored: &Move, hash_move: Option<&Move>, countermove: Option<&Move>, ply: u32) -> i32 {
+        let enabled = |heuristic: Heuristics| self.heuristics.contains(heuristic);
+
+        if enabled(Heuristics::HashMove) && hash_move.is_some_and(|hash_move| same_move(scored, hash_move)) {
+            return HASH_MOVE_SCORE;
+        }
+        if !is_quiet(scored) {
+            if !enabled(Heuristics::Captures) {
+                return 0;
+            }
+
+            let victim = scored.taken.as_ref().map_or(0, |taken| taken.piece_type.materiel_value() as i32) +
+                scored.promotion.map_or(0, |promotion| promotion.materiel_value() as i32);
+
+            return CAPTURE_SCORE + victim * 16 - scored.piece.piece_type.materiel_value() as i32;
+        }
+
+        if enabled(Heuristics::Killers) {
+            let killers = &self.killers[ply as usize];
+            if killers[0].as_ref().is_some_and(|killer| same_move(scored, killer)) {
+                return KILLER_SCORE + 1;
+            }
+            if killers[1].as_ref().is_some_and(|killer| same_move(scored, killer)) {
+                return KILLER_SCORE;
+            }
+        }
+        if enabled(Heuristics::Countermoves) && countermove.is_some_and(|countermove| same_move(scored, countermove)) {
+            return COUNTERMOVE_SCORE;
+        }
+        if enabled(Heuristics::History) {
+            return self.history[history_index(scored)];
+        }
+
+        0
+    }
+
+    /// Learn from `cutoff` failing high at `ply` with `depth` plies left, after the quiet
+    /// moves `tried` failed to. Captures and promotions teach nothing, since they are
+    /// ordered by what they take.
+    pub fn record_cutoff(&mut self, state: &State, cutoff: &Move, ply: u32, depth: u32, tried: &[Move]) {
+        if !is_quiet(cutoff) {
+            return;
+        }
+
+        let killers = &mut self.killers[ply as usize];
+        if !killers[0].as_ref().is_some_and(|killer| same_move(cutoff, killer)) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(cutoff.clone());
+        }
+
+        //  Moves that cut off deep trees matter more, and the moves searched before it
+        //  were worse guesses.
+        let bonus = (depth * depth).min(400) as i32;
+        update_history(&mut self.history[history_index(cutoff)], bonus);
+        for tried_move in tried {
+            update_history(&mut self.history[history_index(tried_move)], -bonus);
+        }
+
+        if let Some(previous) = state.move_history.last() {
+            self.countermoves[countermove_index(&previous.piece, previous.to.index())] = Some(cutoff.clone());
+        }
+    }
+}
+
+pub(super) fn same_move(a: &Move, b: &Move) -> bool {
+    a.from == b.from && a.to == b.to && a.promotion == b.promotion
+}
+
+pub(super) fn is_quiet(checked: &Move) -> bool {
+    checked.taken.is_none() && checked.promotion.is_none()
+}
+
+fn history_index(indexed: &Move) -> usize {
+    (usize::from(indexed.piece.color) * 64 + indexed.from.index()) * 64 + indexed.to.index()
+}
+
+fn countermove_index(piece: &Piece, square: usize) -> usize {
+    (usize::from(piece.color) * 6 + usize::from(piece.piece_type)) * 64 + square
+}
+
+//  Scores move towards the bound they are pushed to in proportion to how far off it they
+//  are, so they stay within HISTORY_MAX however often they are updated.
+fn update_history(score: &mut i32, bonus: i32) {
+    *score += bonus * 64 - *score * bonus.abs() / 400;
+    *score = (*score).clamp(-HISTORY_MAX, HISTORY_MAX);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{ToFEN, ToState, ToUci};
+
+    fn uci_strs(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(|m| m.to_uci().to_string()).collect()
+    }
+
+    #[test]
+    fn test_captures() {
+        //  Taking the queen comes before taking the rook.
+        let state = "4k3/8/8/2q1r3/3P4/8/8/Q2R3K w - - 0 1".to_fen().to_state().unwrap();
+        let mut moves = state.get_legal_moves();
+        let orderer = MoveOrderer::default();
+
+        orderer.order(&state, &mut moves, None, 0);
+        assert_eq!(uci_strs(&moves[..2]), vec!["d4c5", "d4e5"]);
+
+        let hash_move = moves.iter().find(|m| is_quiet(m)).unwrap().clone();
+        orderer.order(&state, &mut moves, Some(&hash_move), 0);
+        assert!(same_move(&moves[0], &hash_move));
+        assert_eq!(uci_strs(&moves[1..3]), vec!["d4c5", "d4e5"]);
+
+        //  With every heuristic off the moves keep their order.
+        let mut unordered = state.get_legal_moves();
+        MoveOrderer::new(Heuristics::none()).order(&state, &mut unordered, Some(&hash_move), 0);
+        assert_eq!(uci_strs(&unordered), uci_strs(&state.get_legal_moves()));
+    }
+
+    #[test]
+    fn test_record_cutoff() {
+        let state = State::default();
+        let moves = state.get_legal_moves();
+        let (first, second, third) = (moves[5].clone(), moves[6].clone(), moves[7].clone());
+        let mut orderer = MoveOrderer::default();
+
+        orderer.record_cutoff(&state, &first, 2, 4, &[]);
+        orderer.record_cutoff(&state, &second, 2, 4, std::slice::from_ref(&third));
+
+        let mut ordered = moves.clone();
+        orderer.order(&state, &mut ordered, None, 2);
+        assert!(same_move(&ordered[0], &second));
+        assert!(same_move(&ordered[1], &first));
+        assert!(same_move(ordered.last().unwrap(), &third));
+
+        //  Killers are kept for their ply only, and are forgotten by the next search while
+        //  history is kept.
+        orderer.order(&state, &mut ordered, None, 3);
+        assert!(same_move(&ordered[0], &first) || same_move(&ordered[0], &second));
+
+        orderer.new_search();
+        assert!(orderer.killers.iter().all(|killers| killers[0].is_none()));
+        assert!(orderer.history[history_index(&second)] > 0);
+
+        //  Captures are ordered by what they take rather than learnt.
+        let mut captures = MoveOrderer::default();
+        let state = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        let capture = state.get_legal_moves().into_iter().find(|m| !is_quiet(m)).unwrap();
+        captures.record_cutoff(&state, &capture, 0, 4, &[]);
+        assert!(captures.killers[0][0].is_none());
+    }
+
+    #[test]
+    fn test_countermoves() {
+        let mut state = State::default();
+        let opening = state.get_legal_moves().into_iter().find(|m| m.to_uci().to_string() == "e2e4").unwrap();
+        state.make_move(&opening);
+
+        let moves = state.get_legal_moves();
+        let reply = moves.iter().find(|m| m.to_uci().to_string() == "c7c5").unwrap().clone();
+        let mut orderer = MoveOrderer::new(Heuristics::Countermoves);
+        orderer.record_cutoff(&state, &reply, 1, 1, &[]);
+
+        let mut ordered = moves.clone();
+        orderer.order(&state, &mut ordered, None, 5);
+        assert!(same_move(&ordered[0], &reply));
+    }
+}
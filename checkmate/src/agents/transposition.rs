@@ -0,0 +1,244 @@
+use std::mem;
+
+use crate::model::Move;
+use super::alpha_beta::{MATE_SCORE, MAX_PLY};
+
+const DEFAULT_MEGABYTES: usize = 16;
+
+/// How a stored score relates to the position's true score, which is only known exactly
+/// when the search didn't cut off or fail low.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high, so the true score is at least the stored score.
+    Lower,
+    /// The search failed low, so the true score is at most the stored score.
+    Upper
+}
+
+/// A stored search result. Mate scores are counted from the stored position, and are
+/// counted from the root again when probed.
+#[derive(Clone, Debug)]
+pub struct TableEntry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>,
+    generation: u8
+}
+
+/// Counts since the table was created or last cleared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    pub probes: u64,
+    pub hits: u64,
+    pub stores: u64,
+    /// Stores that replaced an entry for a different position.
+    pub overwrites: u64
+}
+
+impl TableStats {
+    /// Return the share of probes that found their position, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        match self.probes {
+            0 => 0.0,
+            probes => self.hits as f64 / probes as f64
+        }
+    }
+}
+
+/// `TranspositionTable` remembers search results by [`State::key`](crate::model::State::key)
+/// in a fixed number of slots, one entry per slot.
+///
+/// A store replaces the slot's entry if it's for the same position, from an earlier
+/// search, or searched no deeper, so deep results survive until they go stale.
+#[derive(Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+    generation: u8,
+    stats: TableStats
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEGABYTES)
+    }
+}
+
+impl TranspositionTable {
+    /// Create a table using at most `megabytes` of memory, and at least one entry.
+    pub fn new(megabytes: usize) -> Self {
+        Self::with_entries(megabytes * 1024 * 1024 / mem::size_of::<Option<TableEntry>>())
+    }
+
+    /// Create a table of `entries` slots, rounded down to a power of two.
+    pub fn with_entries(entries: usize) -> Self {
+        let entries = match entries {
+            0 => 1,
+            entries => 1 << entries.ilog2()
+        };
+
+        Self{entries: vec![None; entries], generation: 0, stats: TableStats::default()}
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> TableStats {
+        self.stats
+    }
+
+    /// Drop every entry and reset the statistics, as before a new game.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.generation = 0;
+        self.stats = TableStats::default();
+    }
+
+    /// Mark entries stored so far as coming from an earlier search, so they are replaced
+    /// first.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key & (self.entries.len() as u64 - 1)) as usize
+    }
+
+    /// Return the entry for `key` with mate scores counted from the root, `ply` plies
+    /// above it.
+    pub fn probe(&mut self, key: u64, ply: u32) -> Option<TableEntry> {
+        self.stats.probes += 1;
+
+        let entry = self.get(key, ply)?;
+        self.stats.hits += 1;
+
+        Some(entry)
+    }
+
+    /// Return the entry for `key` like [`TranspositionTable::probe`], without counting it
+    /// in the statistics.
+    pub fn get(&self, key: u64, ply: u32) -> Option<TableEntry> {
+        let entry = self.entries[self.index(key)].as_ref().filter(|entry| entry.key == key)?;
+
+        Some(TableEntry{score: score_from_table(entry.score, ply), ..entry.clone()})
+    }
+
+    /// Store the result of searching the position `key`, `ply` plies from the root.
+    pub fn store(&mut self, key: u64, depth: u32, bound: Bound, score: i32, best_move: Option<Move>, ply: u32) {
+        let index = self.index(key);
+        let generation = self.generation;
+
+        if let Some(existing) = &self.entries[index] {
+            let replace = existing.key == key || existing.generation != generation || depth >= existing.depth;
+            if !replace {
+                return;
+            }
+            if existing.key != key {
+                self.stats.overwrites += 1;
+            }
+        }
+
+        //  Keep the old best move over none, since it's still the best guess for ordering.
+        let best_move = best_move.or_else(|| self.entries[index].as_ref()
+            .filter(|existing| existing.key == key)
+            .and_then(|existing| existing.best_move.clone()));
+
+        self.stats.stores += 1;
+        self.entries[index] = Some(TableEntry{
+            key, depth, bound, score: score_to_table(score, ply), best_move, generation
+        });
+    }
+}
+
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+
+//  Search scores mates from the root, but the same position can be reached at any ply, so
+//  the table scores them from the stored position instead.
+fn score_to_table(score: i32, ply: u32) -> i32 {
+    match score {
+        score if score > MATE_BOUND => score + ply as i32,
+        score if score < -MATE_BOUND => score - ply as i32,
+        score => score
+    }
+}
+
+fn score_from_table(score: i32, ply: u32) -> i32 {
+    match score {
+        score if score > MATE_BOUND => score - ply as i32,
+        score if score < -MATE_BOUND => score + ply as i32,
+        score => score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::State;
+
+    #[test]
+    fn test_probe_and_store() {
+        let mut table = TranspositionTable::with_entries(1000);
+        assert_eq!(table.len(), 512);
+
+        let state = State::default();
+        let first_move = state.get_legal_moves().remove(0);
+
+        assert!(table.probe(state.key(), 0).is_none());
+        table.store(state.key(), 3, Bound::Lower, 25, Some(first_move.clone()), 0);
+
+        let entry = table.probe(state.key(), 0).unwrap();
+        assert_eq!((entry.depth, entry.bound, entry.score), (3, Bound::Lower, 25));
+        assert_eq!(entry.best_move.unwrap().to, first_move.to);
+
+        //  A store without a best move keeps the last one.
+        table.store(state.key(), 4, Bound::Upper, -10, None, 0);
+        assert!(table.probe(state.key(), 0).unwrap().best_move.is_some());
+
+        assert_eq!(table.stats(), TableStats{probes: 3, hits: 2, stores: 2, overwrites: 0});
+        assert!((table.stats().hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+
+        table.clear();
+        assert!(table.get(state.key(), 0).is_none());
+        assert_eq!(table.stats().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut table = TranspositionTable::with_entries(1);
+
+        table.store(1, 5, Bound::Exact, 0, None, 0);
+        table.store(2, 4, Bound::Exact, 0, None, 0);
+        assert!(table.get(1, 0).is_some());
+
+        table.store(2, 5, Bound::Exact, 0, None, 0);
+        assert!(table.get(2, 0).is_some());
+
+        //  Entries from earlier searches give way to anything.
+        table.new_search();
+        table.store(3, 1, Bound::Exact, 0, None, 0);
+        assert!(table.get(3, 0).is_some());
+        assert_eq!(table.stats().overwrites, 2);
+    }
+
+    #[test]
+    fn test_mate_scores() {
+        let mut table = TranspositionTable::with_entries(16);
+
+        //  Mate three plies below a node at ply 4 is mate in three from a node at ply 1.
+        table.store(7, 3, Bound::Exact, MATE_SCORE - 7, None, 4);
+        assert_eq!(table.get(7, 1).unwrap().score, MATE_SCORE - 4);
+
+        table.store(8, 3, Bound::Exact, -MATE_SCORE + 6, None, 4);
+        assert_eq!(table.get(8, 2).unwrap().score, -MATE_SCORE + 4);
+
+        table.store(9, 3, Bound::Exact, 150, None, 4);
+        assert_eq!(table.get(9, 0).unwrap().score, 150);
+    }
+}
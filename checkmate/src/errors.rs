@@ -1,16 +1,52 @@
 use std::fmt;
 
+use crate::model::{Color, Position, CastleMoves};
+use crate::formats::ToAlg;
+
 #[derive(Debug, PartialEq)]
 pub enum ValidationError {
     Parse{token: String},
-    InvalidState{token: String}
+    InvalidState{token: String},
+    /// A move that more than one legal move matches.
+    AmbiguousMove{token: String},
+    /// Another error, at a line and column of the source text.
+    Located{line: usize, column: usize, error: Box<ValidationError>},
+    /// Another error, in the game at `index` (from 0) of a multi-game source.
+    InGame{index: usize, error: Box<ValidationError>},
+    /// Reading the source failed.
+    Io{message: String},
+    /// A placement outside the board.
+    OffBoard{position: Position},
+    /// More than one piece placed on the same square.
+    OccupiedSquare{position: Position},
+    /// A color with no king, or more than one.
+    KingCount{color: Color, count: usize},
+    /// A pawn on the first or eighth rank.
+    PawnOnBackRank{position: Position},
+    /// The color not to move is in check, so the last move was illegal.
+    InactiveColorInCheck{color: Color},
+    /// An en-passant target with no double-stepped pawn in front of it.
+    InvalidEnPassant{position: Position},
+    /// A castle right whose king or rook has left its starting square.
+    InvalidCastleRights{color: Color, castle: CastleMoves}
 }
 
 impl fmt::Display for ValidationError {
     fn fmt(&self, dest: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ValidationError::Parse{token} => write!(dest, "invalid format: {}", token),
-            ValidationError::InvalidState{token} => write!(dest, "invalid state: {}", token)
+            ValidationError::InvalidState{token} => write!(dest, "invalid state: {}", token),
+            ValidationError::AmbiguousMove{token} => write!(dest, "ambiguous move: {}", token),
+            ValidationError::Located{line, column, error} => write!(dest, "{}:{}: {}", line, column, error),
+            ValidationError::InGame{index, error} => write!(dest, "game {}: {}", index, error),
+            ValidationError::Io{message} => write!(dest, "read failed: {}", message),
+            ValidationError::OffBoard{position} => write!(dest, "position off board: {:?}", position),
+            ValidationError::OccupiedSquare{position} => write!(dest, "square occupied twice: {}", position.to_alg()),
+            ValidationError::KingCount{color, count} => write!(dest, "{:?} has {} kings", color, count),
+            ValidationError::PawnOnBackRank{position} => write!(dest, "pawn on back rank: {}", position.to_alg()),
+            ValidationError::InactiveColorInCheck{color} => write!(dest, "{:?} is in check but not to move", color),
+            ValidationError::InvalidEnPassant{position} => write!(dest, "invalid en-passant target: {}", position.to_alg()),
+            ValidationError::InvalidCastleRights{color, castle} => write!(dest, "invalid castle rights for {:?}: {:?}", color, castle)
         }
     }
 }
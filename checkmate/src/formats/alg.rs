@@ -3,7 +3,7 @@ use std::fmt;
 use regex::Regex;
 use lazy_static::lazy_static;
 
-use crate::model::{RANKS, FILES, State, Move, PieceType, Position};
+use crate::model::{RANKS, FILES, State, Move, PieceType, Position, CastleMoves};
 use crate::errors::ValidationError;
 use super::ToPosition;
 use super::format::{ToMove, ToPieceType};
@@ -55,55 +55,63 @@ impl ToPosition for AlgNotation {
 }
 
 impl ToMove for AlgNotation {
+    /// Resolve a SAN move against the legal moves of `state`. Accepts "0-0" for castles,
+    /// promotions without "=", and ignores check suffixes and "!?" style annotations.
     fn to_move(self, state: &State) -> Result<Move, ValidationError> {
         lazy_static! {
-            static ref MOVE_RE: Regex = Regex::new(r"^([A-Z]|[a-h]?)(x?)([a-h][0-8])$").unwrap();
+            static ref MOVE_RE: Regex = Regex::new(concat!(
+                r"^(?:(?P<castle>[O0]-[O0](?:-[O0])?)",
+                r"|(?P<piece>[NBRQK])?(?P<file>[a-h])?(?P<rank>[1-8])?(?P<capture>x)?(?P<dest>[a-h][1-8])(?:=?(?P<promotion>[NBRQ]))?)",
+                r"[+#]?[!?]{0,2}$"
+            )).unwrap();
         }
 
         let move_str = &self.0;
-        let moves = state.get_legal_moves();
-
-        let king_side = move_str == "O-O";
-        if king_side || move_str == "O-O-O" {
-            return Err(ValidationError::InvalidState{token: move_str.to_owned()});
-        }
-
         let captures = match MOVE_RE.captures(move_str) {
             Some(m) => m,
             None => return Err(ValidationError::Parse{token: move_str.to_owned()}),
         };
 
-        let type_str = &captures[1];
-        let capture_str = &captures[2];
-        let dest_str = &captures[3];
-
-        let dest_position = dest_str.to_alg().to_position()?;
-
-        let is_capture = capture_str.len() > 0;
-        if is_capture && state.board[&dest_position].as_ref().is_none() {
-            return Err(ValidationError::InvalidState{token: dest_str.to_owned()})
+        let mut candidates: Vec<Move> = state.get_legal_moves();
+
+        if let Some(castle_str) = captures.name("castle") {
+            let side = match castle_str.as_str().len() {
+                3 => CastleMoves::KingSide,
+                _ => CastleMoves::QueenSide
+            };
+
+            candidates.retain(|m| m.castle_side() == Some(side));
+        } else {
+            let piece_type = match captures.name("piece") {
+                Some(type_str) => type_str.as_str().to_alg().to_piece_type()?,
+                None => PieceType::Pawn
+            };
+            let dest_str = &captures["dest"];
+            let dest_position = dest_str.to_alg().to_position()?;
+            let promotion = match captures.name("promotion") {
+                Some(type_str) => Some(type_str.as_str().to_alg().to_piece_type()?),
+                None => None
+            };
+            let file_char = captures.name("file").and_then(|m| m.as_str().chars().next());
+            let rank_char = captures.name("rank").and_then(|m| m.as_str().chars().next());
+            let is_capture = captures.name("capture").is_some();
+
+            //  Castles are only written as O-O and O-O-O, even when the king lands where a
+            //  king step would.
+            candidates.retain(|m| {
+                m.castle.is_none() &&
+                    m.piece.piece_type == piece_type && m.to == dest_position && m.promotion == promotion &&
+                    file_char.is_none_or(|c| m.from.file_char() == c) &&
+                    rank_char.is_none_or(|c| m.from.rank_char() == c) &&
+                    (!is_capture || m.taken.is_some())
+            });
         }
 
-        let piece_type = match type_str.len() > 0 && type_str.chars().nth(0).unwrap().is_uppercase() {
-            true => type_str.to_alg().to_piece_type()?,
-            false => PieceType::Pawn
-        };
-
-        for check_move in moves {
-            if check_move.to != dest_position {
-                continue;
-            }
-            if check_move.piece.piece_type != piece_type {
-                continue;
-            }
-            if piece_type == PieceType::Pawn && is_capture && check_move.from.rank_char().to_string() != type_str {
-                continue;
-            }
-
-            return Ok(check_move.clone());
+        match candidates.len() {
+            0 => Err(ValidationError::InvalidState{token: move_str.to_owned()}),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(ValidationError::AmbiguousMove{token: move_str.to_owned()})
         }
-
-        Err(ValidationError::InvalidState{token: move_str.to_owned()})
     }
 }
 
@@ -121,12 +129,25 @@ impl ToPieceType for AlgNotation {
     }
 }
 
+/// `ToSAN` writes a move in Standard Algebraic Notation, which depends on the position the
+/// move is played from for disambiguation and check suffixes.
+pub trait ToSAN {
+    fn to_san(&self, state: &State) -> AlgNotation;
+}
+
+/// Writes the parts of SAN that don't need the position: no disambiguation or check suffix.
 impl ToAlg for Move {
     fn to_alg(&self) -> AlgNotation {
-        //  TODO: Disambiguation, castles, check, mate.
-        let type_str = match self.piece.piece_type {
-            PieceType::Pawn => "".to_string(),
-            other => other.to_alg().to_string(),
+        match self.castle_side() {
+            Some(CastleMoves::KingSide) => return "O-O".to_alg(),
+            Some(_) => return "O-O-O".to_alg(),
+            None => ()
+        }
+
+        let type_str = match (self.piece.piece_type, &self.taken) {
+            (PieceType::Pawn, Some(_)) => self.from.file_char().to_string(),
+            (PieceType::Pawn, None) => "".to_string(),
+            (other, _) => other.to_alg().to_string(),
         };
         
         let capture_str = match self.taken {
@@ -136,7 +157,50 @@ impl ToAlg for Move {
 
         let to_position_str = self.to.to_alg().to_string();
 
-        format!("{}{}{}", type_str, capture_str, to_position_str).to_alg()
+        let promotion_str = match self.promotion {
+            Some(promotion) => format!("={}", promotion.to_alg()),
+            None => "".to_string()
+        };
+
+        format!("{}{}{}{}", type_str, capture_str, to_position_str, promotion_str).to_alg()
+    }
+}
+
+impl ToSAN for Move {
+    fn to_san(&self, state: &State) -> AlgNotation {
+        let mut san_str = self.to_alg().to_string();
+
+        //  Other pieces of the same kind that can legally reach the destination. Pawn
+        //  captures already name their file, and there is only one king.
+        if !matches!(self.piece.piece_type, PieceType::Pawn | PieceType::King) {
+            let rivals: Vec<Move> = state.get_legal_moves().into_iter().filter(|m| {
+                m.piece == self.piece && m.to == self.to && m.from != self.from
+            }).collect();
+
+            if !rivals.is_empty() {
+                let disambiguation_str = match (
+                    rivals.iter().all(|m| m.from.file != self.from.file),
+                    rivals.iter().all(|m| m.from.rank != self.from.rank)
+                ) {
+                    (true, _) => self.from.file_char().to_string(),
+                    (false, true) => self.from.rank_char().to_string(),
+                    (false, false) => self.from.to_alg().to_string()
+                };
+
+                san_str.insert_str(1, &disambiguation_str);
+            }
+        }
+
+        let mut next_state = state.clone();
+        next_state.make_move(self);
+        if next_state.is_check_against(next_state.active_color) {
+            san_str.push(match next_state.get_legal_moves().is_empty() {
+                true => '#',
+                false => '+'
+            });
+        }
+
+        san_str.to_alg()
     }
 }
 
@@ -163,6 +227,7 @@ impl ToAlg for Position {
 mod tests {
     use super::*;
     use crate::model::{Piece, Color};
+    use crate::formats::{ToFEN, ToState};
 
     #[test]
     fn test_parse_move() {
@@ -178,4 +243,98 @@ mod tests {
         assert_eq!(develop_knight.from, Position::new(0, 1));
         assert_eq!(develop_knight.to, Position::new(2, 2));
     }
+
+    fn parsed(fen: &str, move_str: &str) -> Result<(String, String, Option<PieceType>), ValidationError> {
+        let state = fen.to_fen().to_state().unwrap();
+        let parsed = move_str.to_alg().to_move(&state)?;
+
+        Ok((parsed.from.to_alg().to_string(), parsed.to.to_alg().to_string(), parsed.promotion))
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let castles = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        for (move_str, dest) in [("O-O", "g1"), ("0-0", "g1"), ("O-O-O", "c1"), ("0-0-0+", "c1")] {
+            assert_eq!(parsed(castles, move_str).unwrap(), ("e1".to_string(), dest.to_string(), None));
+        }
+        for move_str in ["Kg1", "Kc1"] {
+            assert_eq!(parsed(castles, move_str), Err(ValidationError::InvalidState{token: move_str.to_string()}));
+        }
+        assert_eq!(parsed(castles, "Kf1").unwrap().1, "f1");
+
+        let promotion = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(parsed(promotion, "e8=Q+").unwrap().2, Some(PieceType::Queen));
+        assert_eq!(parsed(promotion, "e8N").unwrap().2, Some(PieceType::Knight));
+        assert!(parsed(promotion, "e8").is_err());
+
+        let knights = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(parsed(knights, "Nbd2").unwrap().0, "b1");
+        assert_eq!(parsed(knights, "Nfd2!?").unwrap().0, "f1");
+        assert_eq!(parsed(knights, "Nd2"), Err(ValidationError::AmbiguousMove{token: "Nd2".to_string()}));
+
+        assert_eq!(parsed("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "R1a3").unwrap().0, "a1");
+        assert_eq!(parsed("8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1", "Qh4e1").unwrap().0, "h4");
+
+        //  The capturing pawn is named by its file, not its rank.
+        let captures = "4k3/8/8/2p1p3/3P4/8/8/4K3 w - - 0 1";
+        assert_eq!(parsed(captures, "dxe5").unwrap(), ("d4".to_string(), "e5".to_string(), None));
+        assert_eq!(parsed(captures, "dxc5").unwrap().1, "c5");
+        assert!(parsed(captures, "dxd5").is_err());
+        assert!(parsed(captures, "Nf3").is_err());
+        assert!(matches!(parsed(captures, "Z9"), Err(ValidationError::Parse{..})));
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1"
+        ];
+
+        for fen in fens {
+            let state = fen.to_fen().to_state().unwrap();
+            for legal in state.get_legal_moves() {
+                let parsed = legal.to_san(&state).to_string().to_alg().to_move(&state).unwrap();
+
+                assert_eq!((&parsed.from, &parsed.to, parsed.promotion), (&legal.from, &legal.to, legal.promotion));
+            }
+        }
+    }
+
+    fn san_of(fen: &str, from: &str, to: &str) -> String {
+        let state = fen.to_fen().to_state().unwrap();
+        let (from, to) = (from.to_alg().to_position().unwrap(), to.to_alg().to_position().unwrap());
+        let played = state.get_legal_moves().into_iter().find(|m| m.from == from && m.to == to).unwrap();
+
+        played.to_san(&state).to_string()
+    }
+
+    #[test]
+    fn test_write_san() {
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1", "d2"), "Nbd2");
+        assert_eq!(san_of("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1", "a3"), "R1a3");
+        assert_eq!(san_of("8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1", "h4", "e1"), "Qh4e1");
+        //  The pinned d2 knight can't reach c3, so needn't be told apart.
+        assert_eq!(san_of("4k3/8/8/8/1b6/8/3N4/1N2K3 w - - 0 1", "b1", "c3"), "Nc3");
+
+        assert_eq!(san_of("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1", "g1"), "O-O");
+        assert_eq!(san_of("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8", "c8"), "O-O-O");
+
+        assert_eq!(san_of("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4", "d5"), "exd5");
+        assert_eq!(san_of("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", "h5", "f7"), "Qxf7#");
+    }
+
+    #[test]
+    fn test_write_promotion() {
+        let state = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        let promotions: Vec<String> = state.get_legal_moves_from(&Position::new(6, 4)).iter().map(|m| {
+            m.to_san(&state).to_string()
+        }).collect();
+
+        for expected in ["e8=Q+", "e8=R+", "e8=B", "e8=N"] {
+            assert!(promotions.contains(&expected.to_string()));
+        }
+    }
 }
@@ -0,0 +1,359 @@
+use std::fmt;
+use std::collections::BTreeMap;
+
+use readonly;
+
+use crate::model::{State, Move};
+use crate::errors::ValidationError;
+use super::format::ToMove;
+use super::alg::{ToAlg, ToSAN};
+use super::fen::{ToFEN, FENMode};
+
+//  Extended Position Description: the first four FEN fields followed by semicolon
+//  terminated operations, each an opcode and its operands.
+
+/// Opcodes whose operands are SAN moves played from the record's position.
+const MOVE_OPCODES: [&str; 3] = ["am", "bm", "sm"];
+/// Opcodes whose operands are a line of SAN moves, each played after the last.
+const LINE_OPCODES: [&str; 2] = ["pm", "pv"];
+
+pub trait ToEpd {
+    fn to_epd(&self) -> EpdNotation;
+}
+
+impl<T: AsRef<str>> ToEpd for T {
+    fn to_epd(&self) -> EpdNotation {
+        EpdNotation::new(self)
+    }
+}
+
+pub struct EpdNotation(String);
+
+impl EpdNotation {
+    pub fn new(data: impl AsRef<str>) -> Self {
+        Self(data.as_ref().to_string())
+    }
+
+    pub fn to_record(&self) -> Result<EpdRecord, ValidationError> {
+        //  The operations are left untouched, since quoted operands may hold runs of
+        //  whitespace.
+        let mut operations_str = self.0.as_str();
+        let mut position_strs = Vec::with_capacity(4);
+        while position_strs.len() < 4 {
+            operations_str = operations_str.trim_start();
+            let end = operations_str.find(char::is_whitespace).unwrap_or(operations_str.len());
+            if end == 0 {
+                return Err(ValidationError::InvalidState{token: self.0.clone()});
+            }
+
+            position_strs.push(&operations_str[..end]);
+            operations_str = &operations_str[end..];
+        }
+
+        let raw_operations = parse_operations(operations_str)?;
+
+        //  The clock opcodes stand in for the FEN clock fields.
+        let clock_of = |opcode: &str, default: &str| match raw_operations.get(opcode) {
+            Some(operands) if operands.len() == 1 => Ok(operands[0].0.clone()),
+            Some(_) => Err(ValidationError::Parse{token: opcode.to_string()}),
+            None => Ok(default.to_string())
+        };
+        let fen = format!("{} {} {}", position_strs.join(" "), clock_of("hmvc", "0")?, clock_of("fmvn", "1")?);
+        let state = fen.to_fen().to_state_with(FENMode::Lenient)?;
+
+        let mut operations = BTreeMap::new();
+        for (opcode, raw_operands) in raw_operations {
+            let operands = resolve_operands(&state, &opcode, raw_operands)?;
+            operations.insert(opcode, operands);
+        }
+
+        Ok(EpdRecord{state, operations})
+    }
+}
+
+impl fmt::Display for EpdNotation {
+    fn fmt(&self, dest: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(dest, "{}", self.0)
+    }
+}
+
+/// A single operand of an EPD operation.
+#[derive(Clone, Debug)]
+pub enum EpdOperand {
+    /// A move, from the operands of `am`, `bm`, `pm`, `pv` and `sm`.
+    Move(Move),
+    Str(String),
+    Int(i64),
+    /// Any other unquoted operand.
+    Symbol(String)
+}
+
+/// A position and the operations describing it, ordered by opcode.
+#[readonly::make]
+#[derive(Clone)]
+pub struct EpdRecord {
+    pub state: State,
+    pub operations: BTreeMap<String, Vec<EpdOperand>>
+}
+
+impl EpdRecord {
+    pub fn new(state: State, operations: BTreeMap<String, Vec<EpdOperand>>) -> Self {
+        Self{state, operations}
+    }
+
+    pub fn operands(&self, opcode: &str) -> Option<&Vec<EpdOperand>> {
+        self.operations.get(opcode)
+    }
+
+    fn moves_of(&self, opcode: &str) -> Vec<&Move> {
+        self.operands(opcode).into_iter().flatten().filter_map(|o| match o {
+            EpdOperand::Move(m) => Some(m),
+            _ => None
+        }).collect()
+    }
+
+    fn str_of(&self, opcode: &str) -> Option<&str> {
+        match self.operands(opcode)?.first()? {
+            EpdOperand::Str(value) | EpdOperand::Symbol(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn int_of(&self, opcode: &str) -> Option<i64> {
+        match self.operands(opcode)?.first()? {
+            EpdOperand::Int(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn best_moves(&self) -> Vec<&Move> {
+        self.moves_of("bm")
+    }
+
+    pub fn avoid_moves(&self) -> Vec<&Move> {
+        self.moves_of("am")
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.str_of("id")
+    }
+
+    /// Return comment `index`, from the opcodes `c0` to `c9`.
+    pub fn comment(&self, index: usize) -> Option<&str> {
+        self.str_of(&format!("c{}", index))
+    }
+
+    /// Return the depth of the analysis, in plies.
+    pub fn analysis_depth(&self) -> Option<i64> {
+        self.int_of("acd")
+    }
+
+    /// Return the evaluation of the position in centipawns, for the side to move.
+    pub fn centipawn_eval(&self) -> Option<i64> {
+        self.int_of("ce")
+    }
+}
+
+//  Split operations into opcodes and raw operands, which are flagged when quoted so
+//  strings that look like numbers stay strings. Quoted operands escape quotes and
+//  backslashes with a backslash.
+fn parse_operations(operations_str: &str) -> Result<BTreeMap<String, Vec<(String, bool)>>, ValidationError> {
+    let mut operations = BTreeMap::new();
+    let mut chars = operations_str.chars().peekable();
+    let mut current: Vec<(String, bool)> = Vec::new();
+
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+
+        match chars.next() {
+            None => break,
+            Some(';') => {
+                if current.is_empty() {
+                    return Err(ValidationError::Parse{token: ";".to_string()});
+                }
+
+                let (opcode, _) = current.remove(0);
+                operations.insert(opcode, std::mem::take(&mut current));
+            },
+            Some('"') => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => value.push(c),
+                            None => return Err(ValidationError::Parse{token: format!("\"{}\\", value)})
+                        },
+                        Some(c) => value.push(c),
+                        None => return Err(ValidationError::Parse{token: format!("\"{}", value)})
+                    }
+                }
+
+                current.push((value, true));
+            },
+            Some(c) => {
+                let mut value = c.to_string();
+                while let Some(next) = chars.peek() {
+                    if next.is_whitespace() || *next == ';' {
+                        break;
+                    }
+
+                    value.push(*next);
+                    chars.next();
+                }
+
+                current.push((value, false));
+            }
+        }
+    }
+
+    //  The last operation must be terminated too.
+    if let Some((opcode, _)) = current.first() {
+        return Err(ValidationError::Parse{token: opcode.clone()});
+    }
+
+    Ok(operations)
+}
+
+fn resolve_operands(state: &State, opcode: &str, raw_operands: Vec<(String, bool)>) -> Result<Vec<EpdOperand>, ValidationError> {
+    if MOVE_OPCODES.contains(&opcode) {
+        return raw_operands.iter().map(|(san, _)| Ok(EpdOperand::Move(san.to_alg().to_move(state)?))).collect();
+    }
+    if LINE_OPCODES.contains(&opcode) {
+        let mut line_state = state.clone();
+
+        return raw_operands.iter().map(|(san, _)| {
+            let played = san.to_alg().to_move(&line_state)?;
+            line_state.make_move(&played);
+
+            Ok(EpdOperand::Move(played))
+        }).collect();
+    }
+
+    Ok(raw_operands.into_iter().map(|(value, quoted)| match (quoted, value.parse::<i64>()) {
+        (true, _) => EpdOperand::Str(value),
+        (false, Ok(number)) => EpdOperand::Int(number),
+        (false, Err(_)) => EpdOperand::Symbol(value)
+    }).collect())
+}
+
+impl ToEpd for EpdRecord {
+    fn to_epd(&self) -> EpdNotation {
+        let fen = self.state.to_fen().to_string();
+        let position_str = fen.split(' ').take(4).collect::<Vec<&str>>().join(" ");
+
+        let mut operation_strs = vec![position_str];
+        for (opcode, operands) in &self.operations {
+            let mut line_state = self.state.clone();
+            let mut operation_str = opcode.clone();
+
+            for operand in operands {
+                let operand_str = match operand {
+                    EpdOperand::Move(played) => {
+                        let san = played.to_san(&line_state).to_string();
+                        if LINE_OPCODES.contains(&opcode.as_str()) {
+                            line_state.make_move(played);
+                        }
+
+                        san
+                    },
+                    EpdOperand::Str(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+                    EpdOperand::Int(value) => value.to_string(),
+                    EpdOperand::Symbol(value) => value.clone()
+                };
+
+                operation_str.push(' ');
+                operation_str.push_str(&operand_str);
+            }
+
+            operation_str.push(';');
+            operation_strs.push(operation_str);
+        }
+
+        operation_strs.join(" ").to_epd()
+    }
+}
+
+impl ToEpd for State {
+    fn to_epd(&self) -> EpdNotation {
+        EpdRecord::new(self.clone(), BTreeMap::new()).to_epd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Position;
+
+    const WAC_1: &str = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; c0 "mate; in three"; id "WAC.001";"#;
+
+    #[test]
+    fn test_parse_record() {
+        let record = WAC_1.to_epd().to_record().unwrap();
+
+        assert_eq!(record.best_moves().len(), 1);
+        assert_eq!(record.best_moves()[0].to, Position::new(5, 6));
+        assert_eq!(record.id(), Some("WAC.001"));
+        assert_eq!(record.comment(0), Some("mate; in three"));
+        assert_eq!(record.comment(1), None);
+        assert_eq!((record.state.halfmove_clock, record.state.fullmove_number), (0, 1));
+
+        let record = "4k3/8/8/8/8/8/4P3/4K3 w - - am Kd1 Kf1; acd 12; ce -35; hmvc 7; fmvn 40; pv e4 Kd7 e5;".to_epd().to_record().unwrap();
+
+        assert_eq!(record.avoid_moves().len(), 2);
+        assert_eq!(record.analysis_depth(), Some(12));
+        assert_eq!(record.centipawn_eval(), Some(-35));
+        assert_eq!((record.state.halfmove_clock, record.state.fullmove_number), (7, 40));
+        assert_eq!(record.operands("pv").unwrap().len(), 3);
+
+        let record = r#"4k3/8/8/8/8/8/4P3/4K3 w - - c0 "say \"hi\"; then C:\\ leave";"#.to_epd().to_record().unwrap();
+        assert_eq!(record.comment(0), Some(r#"say "hi"; then C:\ leave"#));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("4k3/8/8/8/8/8/4P3/4K3 w -".to_epd().to_record().is_err());
+        assert!("4k3/8/8/8/8/8/4P3/4K3 w - - bm Ke3".to_epd().to_record().is_err());
+        assert!("4k3/8/8/8/8/8/4P3/4K3 w - - bm e5;".to_epd().to_record().is_err());
+        assert!("4k3/8/8/8/8/8/4P3/4K3 w - - id \"open;".to_epd().to_record().is_err());
+        assert!("4k3/8/8/8/8/8/4P3/4K3 w - - ;".to_epd().to_record().is_err());
+        assert!("4k3/8/8/8/8/8/4P3/4K3 w - - id \"open\\".to_epd().to_record().is_err());
+    }
+
+    #[test]
+    fn test_write_record() {
+        assert_eq!(State::default().to_epd().to_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+
+        let state = State::default();
+        let mut operations = BTreeMap::new();
+        operations.insert("id".to_string(), vec![EpdOperand::Str("start".to_string())]);
+        operations.insert("bm".to_string(), vec![
+            EpdOperand::Move("e4".to_alg().to_move(&state).unwrap()),
+            EpdOperand::Move("d4".to_alg().to_move(&state).unwrap())
+        ]);
+        operations.insert("ce".to_string(), vec![EpdOperand::Int(20)]);
+        operations.insert("c0".to_string(), vec![EpdOperand::Str(r#"a "quoted"; \ string"#.to_string())]);
+
+        assert_eq!(
+            EpdRecord::new(state, operations).to_epd().to_string(),
+            r#"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4 d4; c0 "a \"quoted\"; \\ string"; ce 20; id "start";"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let records = [
+            WAC_1,
+            "4k3/8/8/8/8/8/4P3/4K3 w - - acd 12; am Kd1 Kf1; ce -35; pv e4 Kd7 e5;",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id \"scholar\";",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - c0 \"two  spaces\";",
+            r#"4k3/8/8/8/8/8/4P3/4K3 w - - c0 "say \"hi\"; then C:\\ leave";"#
+        ];
+
+        for record_str in records {
+            assert_eq!(record_str.to_epd().to_record().unwrap().to_epd().to_string(), record_str);
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use std::fmt;
+use std::cmp::Ordering;
 
 use regex::Regex;
 use lazy_static::lazy_static;
 
-use crate::model::{State, StateBuilder, Color, CastleMoves, Piece, Position};
+use crate::model::{State, StateBuilder, Board, BoardBuilder, Color, CastleMoves, Piece, PieceType, Position};
 use crate::errors::ValidationError;
-use super::format::{ToState, ToPieceType, ToPiece};
+use super::format::{ToState, ToPieceType, ToPiece, ToPosition};
 use super::alg::ToAlg;
 
 pub trait ToFEN {
@@ -49,47 +50,45 @@ impl ToPiece for FENotation {
     }
 }
 
-impl ToState for FENotation {
-    fn to_state(self) -> Result<State, ValidationError> {
-        lazy_static! {
-            static ref STATE_RE: Regex = Regex::new(r"^([/rnbqkpRNBQKP0-9]+)\s([wb])\s([kqKQ]+)\s((?:[a-h][0-9])|-)\s([0-9]+)\s([0-9]+)$").unwrap();
+/// How closely a FEN string must follow the standard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FENMode {
+    /// Exactly six fields separated by single spaces, castle rights in `KQkq` order, white
+    /// first and kingside first, and no consecutive digits within a rank.
+    Strict,
+    /// Surrounding and repeated whitespace, missing clocks and castle rights in any order
+    /// are accepted.
+    Lenient
+}
+
+impl FENotation {
+    /// Parse a piece placement field alone.
+    pub fn to_board(&self) -> Result<Board, ValidationError> {
+        let mut builder = BoardBuilder::new();
+        for (piece, position) in parse_placement(&self.0, FENMode::Strict)? {
+            builder.place_piece(piece, position);
         }
 
-        let state_str = &self.0;
-        let mut builder = StateBuilder::new();
+        builder.try_build()
+    }
 
-        let captures = match STATE_RE.captures(state_str) {
-            Some(m) => m,
-            None => return Err(ValidationError::InvalidState{token: self.0}),
+    /// Parse the state, following the standard as closely as `mode` requires.
+    pub fn to_state_with(self, mode: FENMode) -> Result<State, ValidationError> {
+        let fields: Vec<&str> = match mode {
+            FENMode::Strict => self.0.split(' ').collect(),
+            FENMode::Lenient => self.0.split_whitespace().collect()
         };
 
-        let positions_str = &captures[1];
-        let active_color_str = &captures[2];
-        let allowed_castles_str = &captures[3];
-        let en_passant_str = &captures[4];
-        let draw_clock_str = &captures[5];
-        let turn_clock_str = &captures[6];
-
-        let ranks_strs: Vec<&str> = positions_str.split("/").collect();
-        if ranks_strs.len() != 8 {
-            return Err(ValidationError::Parse{token: positions_str.to_owned()});
-        }
-
-        for (inv_rank_idx, rank_str) in ranks_strs.iter().enumerate() {
-            let mut file_idx = 0;
-            for placement_char in rank_str.chars() {
-                let placement_str = placement_char.to_string();
-                if let Ok(skip_count) = placement_str.parse::<usize>() {
-                    file_idx += skip_count;
-                    continue;
-                }
-
-                let position = Position::new(7 - inv_rank_idx, file_idx);
-                let piece = placement_str.to_fen().to_piece()?;
+        let (placement_str, active_color_str, allowed_castles_str, en_passant_str, clocks_strs) = match (mode, fields.as_slice()) {
+            (_, [p, a, c, e, h, f]) => (*p, *a, *c, *e, Some((*h, *f))),
+            (FENMode::Lenient, [p, a, c, e]) => (*p, *a, *c, *e, None),
+            _ => return Err(ValidationError::InvalidState{token: self.0})
+        };
 
-                builder.board_builder().place_piece(piece, position);
-                file_idx += 1;
-            }
+        let placements = parse_placement(placement_str, mode)?;
+        let mut builder = StateBuilder::new();
+        for (piece, position) in &placements {
+            builder.board_builder().place_piece(piece.clone(), position.clone());
         }
 
         let active_color = match active_color_str {
@@ -97,27 +96,201 @@ impl ToState for FENotation {
             "b" => Color::Black,
             _ => return Err(ValidationError::Parse{token: active_color_str.to_owned()}),
         };
+        let (allowed_castles, rook_files) = parse_castles(allowed_castles_str, &placements, mode)?;
+        for (color, side, file) in rook_files {
+            builder.set_castle_rook_file(color, side, file);
+        }
+        let en_passant_target = match en_passant_str {
+            "-" => None,
+            target_str => Some(target_str.to_alg().to_position()?)
+        };
+        builder.set_abstract_history(active_color, allowed_castles, en_passant_target);
 
-        let mut allowed_castles = [CastleMoves::none(), CastleMoves::none()];
-        for allowed_char in allowed_castles_str.chars() {
-            let color_idx = match allowed_char.is_uppercase() {
-                true => 0,
-                false => 1
+        //  Without clocks the builder assumes a fresh game.
+        if let Some((halfmove_clock_str, fullmove_number_str)) = clocks_strs {
+            let halfmove_clock = match halfmove_clock_str.parse::<u32>() {
+                Ok(c) => c,
+                Err(_) => return Err(ValidationError::Parse{token: halfmove_clock_str.to_owned()}),
             };
-
-            let which = match allowed_char.to_string().to_lowercase().as_str() {
-                "k" => CastleMoves::KingSide,
-                "q" => CastleMoves::QueenSide,
-                _ => return Err(ValidationError::Parse{token: allowed_castles_str.to_owned()}),
+            let fullmove_number = match fullmove_number_str.parse::<u32>() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(ValidationError::Parse{token: fullmove_number_str.to_owned()}),
             };
+            builder.set_clocks(halfmove_clock, fullmove_number);
+        }
+
+        builder.try_build()
+    }
+}
+
+fn parse_placement(placement_str: &str, mode: FENMode) -> Result<Vec<(Piece, Position)>, ValidationError> {
+    let ranks_strs: Vec<&str> = placement_str.split('/').collect();
+    if ranks_strs.len() != 8 {
+        return Err(ValidationError::Parse{token: placement_str.to_owned()});
+    }
+
+    let mut placements = Vec::new();
+    for (inv_rank_idx, rank_str) in ranks_strs.iter().enumerate() {
+        let rank_error = || ValidationError::Parse{token: rank_str.to_string()};
+
+        let mut file_idx = 0;
+        let mut last_skipped = false;
+        for placement_char in rank_str.chars() {
+            if let Some(skip_count) = placement_char.to_digit(10) {
+                let repeated = last_skipped && mode == FENMode::Strict;
+                if skip_count == 0 || repeated {
+                    return Err(rank_error());
+                }
+
+                file_idx += skip_count as usize;
+                last_skipped = true;
+                continue;
+            }
+            if file_idx > 7 {
+                return Err(rank_error());
+            }
+
+            let piece = placement_char.to_string().to_fen().to_piece()?;
+            placements.push((piece, Position::new(7 - inv_rank_idx, file_idx)));
+            file_idx += 1;
+            last_skipped = false;
+        }
+
+        if file_idx != 8 {
+            return Err(rank_error());
+        }
+    }
+
+    Ok(placements)
+}
+
+/// The file of the rook a color castles with on a side.
+type CastleRookFile = (Color, CastleMoves, usize);
+
+/// Parse castle rights in any of standard, X-FEN and Shredder-FEN notation, returning the
+/// rights and the rook file each one refers to.
+///
+/// `K` and `Q` name the outermost rook on their side of the king, and a file letter names
+/// the rook on that file, which X-FEN uses for an inner rook. Rights naming a missing rook
+/// fall back to the corner files, for validation to reject.
+fn parse_castles(
+    allowed_castles_str: &str, placements: &[(Piece, Position)], mode: FENMode
+) -> Result<([CastleMoves; 2], Vec<CastleRookFile>), ValidationError> {
+    lazy_static! {
+        static ref STRICT_CASTLES_RE: Regex = Regex::new(r"^(-|[KQA-H]*[kqa-h]*)$").unwrap();
+    }
+
+    let castles_error = || ValidationError::Parse{token: allowed_castles_str.to_owned()};
+    let mut allowed_castles = [CastleMoves::none(), CastleMoves::none()];
+    let mut rook_files = Vec::new();
+    if allowed_castles_str == "-" {
+        return Ok((allowed_castles, rook_files));
+    }
+    if allowed_castles_str.is_empty() || (mode == FENMode::Strict && !STRICT_CASTLES_RE.is_match(allowed_castles_str)) {
+        return Err(castles_error());
+    }
+
+    for allowed_char in allowed_castles_str.chars() {
+        let color = match allowed_char.is_uppercase() {
+            true => Color::White,
+            false => Color::Black
+        };
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        let back_rank_files = |piece_type: PieceType| placements.iter().filter(move |(piece, position)| {
+            *piece == Piece::new(color, piece_type) && position.rank == back_rank
+        }).map(|(_, position)| position.file);
+
+        let king_file = back_rank_files(PieceType::King).next().unwrap_or(4);
+        let (side, rook_file) = match allowed_char.to_ascii_lowercase() {
+            'k' => (CastleMoves::KingSide, back_rank_files(PieceType::Rook).filter(|f| *f > king_file).max().unwrap_or(7)),
+            'q' => (CastleMoves::QueenSide, back_rank_files(PieceType::Rook).filter(|f| *f < king_file).min().unwrap_or(0)),
+            file_char @ 'a'..='h' => {
+                let file = (file_char as u8 - b'a') as usize;
+                match file.cmp(&king_file) {
+                    Ordering::Greater => (CastleMoves::KingSide, file),
+                    Ordering::Less => (CastleMoves::QueenSide, file),
+                    Ordering::Equal => return Err(castles_error())
+                }
+            },
+            _ => return Err(castles_error()),
+        };
 
-            allowed_castles[color_idx] = allowed_castles[color_idx].or(which);
+        let color_idx: usize = color.into();
+        let repeated = allowed_castles[color_idx].contains(side) ||
+            (side == CastleMoves::KingSide && allowed_castles[color_idx].contains(CastleMoves::QueenSide));
+        if repeated && mode == FENMode::Strict {
+            return Err(castles_error());
         }
 
-        //  TODO: En-passant.
-        builder.set_abstract_history(active_color, allowed_castles, None);
+        allowed_castles[color_idx] = allowed_castles[color_idx].or(side);
+        rook_files.push((color, side, rook_file));
+    }
+
+    Ok((allowed_castles, rook_files))
+}
+
+impl ToState for FENotation {
+    /// Parse the state in [`FENMode::Strict`].
+    fn to_state(self) -> Result<State, ValidationError> {
+        self.to_state_with(FENMode::Strict)
+    }
+}
+
+/// `ToShredderFEN` writes FEN naming the rook of every castle right by its file, as in
+/// "HAha", rather than by side.
+pub trait ToShredderFEN {
+    fn to_shredder_fen(&self) -> FENotation;
+}
+
+impl ToShredderFEN for State {
+    fn to_shredder_fen(&self) -> FENotation {
+        let fen = self.to_fen().to_string();
+        let mut fields: Vec<&str> = fen.split(' ').collect();
+
+        let shredder_castles_str = castles_str(self, true);
+        fields[2] = &shredder_castles_str;
+
+        fields.join(" ").to_fen()
+    }
+}
+
+/// Write the castle rights of `state`, white first and kingside first. Unless `shredder`,
+/// rights to the outermost rook on a side are written `K` or `Q` and others by file, as
+/// in X-FEN, which is plain FEN for standard chess.
+fn castles_str(state: &State, shredder: bool) -> String {
+    let mut castles_str = String::new();
+
+    for color in [Color::White, Color::Black] {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        let rook_files: Vec<usize> = (0..8).filter(|file| {
+            state.board[&Position::new(back_rank, *file)] == Some(Piece::new(color, PieceType::Rook))
+        }).collect();
+
+        for (side, side_char) in [(CastleMoves::KingSide, 'k'), (CastleMoves::QueenSide, 'q')] {
+            if !state.get_allowed_castles(color).contains(side) {
+                continue;
+            }
+
+            let rook_file = state.get_castle_rook_file(color, side);
+            let outermost = match side == CastleMoves::KingSide {
+                true => rook_files.iter().all(|f| *f <= rook_file),
+                false => rook_files.iter().all(|f| *f >= rook_file)
+            };
+            let castle_char = match shredder || !outermost {
+                true => (b'a' + rook_file as u8) as char,
+                false => side_char
+            };
+
+            castles_str.push(match color {
+                Color::White => castle_char.to_ascii_uppercase(),
+                Color::Black => castle_char
+            });
+        }
+    }
 
-        Ok(builder.build())
+    match castles_str.is_empty() {
+        true => "-".to_string(),
+        false => castles_str
     }
 }
 
@@ -130,74 +303,63 @@ impl ToFEN for Color {
     }
 }
 
-impl ToFEN for State {
+impl ToFEN for Piece {
+    fn to_fen(&self) -> FENotation {
+        let type_str = self.piece_type.to_alg().to_string();
+
+        match self.color {
+            Color::White => type_str,
+            Color::Black => type_str.to_lowercase()
+        }.to_fen()
+    }
+}
+
+impl ToFEN for Board {
+    /// Write the piece placement field.
     fn to_fen(&self) -> FENotation {
         let mut ranks_strs: Vec<String> = Vec::new();
 
         for rank_idx in (0..8).rev() {
-            let mut rank_strs: Vec<String> = Vec::new();
+            let mut rank_str = String::new();
             let mut empties = 0;
 
             for file_idx in 0..8 {
-                let position = Position::new(rank_idx, file_idx);
-
-                if let Some(piece) = self.board[&position].as_ref() {
-                    if empties > 0 {
-                        rank_strs.push(format!("{}", empties));
-                        empties = 0;
-                    }
-
-                    let mut type_str = piece.piece_type.to_alg().to_string();
-                    if piece.color == Color::Black {
-                        type_str = type_str.to_lowercase();
-                    }
-                    rank_strs.push(type_str);
-                    continue;
+                match self[&Position::new(rank_idx, file_idx)].as_ref() {
+                    Some(piece) => {
+                        if empties > 0 {
+                            rank_str.push_str(&empties.to_string());
+                            empties = 0;
+                        }
+
+                        rank_str.push_str(&piece.to_fen().to_string());
+                    },
+                    None => empties += 1
                 }
-
-                empties += 1;
             }
 
             if empties > 0 {
-                rank_strs.push(format!("{}", empties));
+                rank_str.push_str(&empties.to_string());
             }
-            ranks_strs.push(rank_strs.join(""));
+            ranks_strs.push(rank_str);
         }
 
-        let mut allowed_castles_strs: Vec<String> = Vec::new();
-        let mut add_castles = |color: Color| {
-            let mask = self.get_allowed_castles(color);
-
-            if mask.contains(CastleMoves::KingSide) {
-                let mut mask_char = "k".to_string();
-                if color == Color::White {
-                    mask_char = mask_char.to_uppercase();
-                }
-
-                allowed_castles_strs.push(mask_char);
-            }
-            if mask.contains(CastleMoves::QueenSide) {
-                let mut mask_char = "q".to_string();
-                if color == Color::White {
-                    mask_char = mask_char.to_uppercase();
-                }
+        ranks_strs.join("/").to_fen()
+    }
+}
 
-                allowed_castles_strs.push(mask_char);
-            }
-        };
-        add_castles(Color::White);
-        add_castles(Color::Black);
+impl ToFEN for State {
+    fn to_fen(&self) -> FENotation {
+        let allowed_castles_str = castles_str(self, false);
 
         let mut en_passant_str = "-".to_string();
         if let Some(en_passant_target) = self.get_en_passant_position() {
             en_passant_str = en_passant_target.to_alg().to_string();
         }
 
-        // TODO: Clocks.
         format!(
-            "{} {} {} {} 0 {}",
-            ranks_strs.join("/"), self.active_color.to_fen(), allowed_castles_strs.join(""),
-            en_passant_str, (self.move_history.len() / 2) + 1
+            "{} {} {} {} {} {}",
+            self.board.to_fen(), self.active_color.to_fen(), allowed_castles_str,
+            en_passant_str, self.halfmove_clock, self.fullmove_number
         ).to_fen()
     }
 }
@@ -205,7 +367,6 @@ impl ToFEN for State {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::PieceType;
 
     #[test]
     fn test_parse_state() {
@@ -235,4 +396,128 @@ mod tests {
 
         assert_eq!(state.to_fen().to_string(), "rnbqkbnr/pppppppp/8/8/2P5/8/PP1PPPPP/RNBQKBNR b KQkq c3 0 1");
     }
+
+    #[test]
+    fn test_clocks() {
+        let fen = "r3k3/8/8/8/8/8/8/4K2R w Kq - 37 52";
+        let state = fen.to_fen().to_state().unwrap();
+
+        assert_eq!((state.halfmove_clock, state.fullmove_number), (37, 52));
+        assert_eq!(state.to_fen().to_string(), fen);
+
+        assert!("r3k3/8/8/8/8/8/8/4K2R w Kq - 37 0".to_fen().to_state().is_err());
+    }
+
+    #[test]
+    fn test_en_passant() {
+        let state = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3".to_fen().to_state().unwrap();
+
+        assert_eq!(state.get_en_passant_position(), Some(&Position::new(2, 4)));
+        assert!(state.get_legal_moves().iter().any(|m| m.to == Position::new(2, 4)));
+    }
+
+    #[test]
+    fn test_placement_errors() {
+        let error_of = |fen: &str| fen.to_fen().to_state().err().unwrap();
+
+        assert_eq!(error_of("4k3/8/8/8/8/8/8/4K2 w - - 0 1"), ValidationError::Parse{token: "4K2".to_string()});
+        assert_eq!(error_of("4k3/8/8/8/8/8/8/4K2RR w - - 0 1"), ValidationError::Parse{token: "4K2RR".to_string()});
+        assert_eq!(error_of("4k3/9/8/8/8/8/8/4K3 w - - 0 1"), ValidationError::Parse{token: "9".to_string()});
+        assert_eq!(error_of("4k3/44/8/8/8/8/8/4K3 w - - 0 1"), ValidationError::Parse{token: "44".to_string()});
+        assert_eq!(error_of("4k3/8/8/8/8/8/4K3 w - - 0 1"), ValidationError::Parse{token: "4k3/8/8/8/8/8/4K3".to_string()});
+        assert_eq!(error_of("4k3/8/8/8/8/8/8/4X3 w - - 0 1"), ValidationError::Parse{token: "X".to_string()});
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+        assert!(fen.to_fen().to_state().is_ok());
+        assert!(format!("{} ", fen).to_fen().to_state().is_err());
+        assert!(fen.replace(" w ", "  w ").to_fen().to_state().is_err());
+        assert!(fen.replace(" 0 1", "").to_fen().to_state().is_err());
+        assert!(fen.replace("KQkq", "kqKQ").to_fen().to_state().is_err());
+        assert!(fen.replace("KQkq", "KKQkq").to_fen().to_state().is_err());
+        assert!(fen.replace("KQkq", "").to_fen().to_state().is_err());
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let lenient = |fen: &str| fen.to_fen().to_state_with(FENMode::Lenient).unwrap();
+
+        let state = lenient("  r3k2r/8/8/8/8/8/8/R3K2R   b qkQK -\n");
+        assert_eq!(state.active_color, Color::Black);
+        assert_eq!(state.get_allowed_castles(Color::White), CastleMoves::KingSide.or(CastleMoves::QueenSide));
+        assert_eq!((state.halfmove_clock, state.fullmove_number), (0, 1));
+
+        assert_eq!(lenient("4k3/8/8/8/8/8/8/4K3 w - - 12 40\t").halfmove_clock, 12);
+        assert_eq!(lenient("4k3/8/8/8/8/8/8/211K3 w - -").to_fen().to_string(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert!("4k3/8/8/8/8/8/8/4K3 w -".to_fen().to_state_with(FENMode::Lenient).is_err());
+        assert!("4k3/8/8/8/8/8/8/4K3 w - - 0".to_fen().to_state_with(FENMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w Kq - 4 17",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 b - - 99 212"
+        ];
+
+        for fen in fens {
+            assert_eq!(fen.to_fen().to_state().unwrap().to_fen().to_string(), fen);
+            assert_eq!(fen.to_fen().to_state_with(FENMode::Lenient).unwrap().to_fen().to_string(), fen);
+        }
+    }
+
+    #[test]
+    fn test_shredder_fen() {
+        assert_eq!(State::default().to_shredder_fen().to_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let state = fen.to_fen().to_state().unwrap();
+
+        assert_eq!(state.get_castle_rook_file(Color::White, CastleMoves::KingSide), 7);
+        assert_eq!(state.get_castle_rook_file(Color::Black, CastleMoves::QueenSide), 5);
+        assert_eq!(state.to_shredder_fen().to_string(), fen);
+        assert_eq!(state.to_fen().to_string(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+        assert_eq!(state.to_fen().to_string().to_fen().to_state().unwrap().to_shredder_fen().to_string(), fen);
+    }
+
+    #[test]
+    fn test_x_fen() {
+        //  The b1 rook holds the queenside right, so it can't be written as "Q".
+        let fen = "r3k3/8/8/8/8/8/8/RR2K3 w Bq - 0 1";
+        let state = fen.to_fen().to_state().unwrap();
+
+        assert_eq!(state.get_castle_rook_file(Color::White, CastleMoves::QueenSide), 1);
+        assert_eq!(state.to_fen().to_string(), fen);
+        assert_eq!(state.to_shredder_fen().to_string(), "r3k3/8/8/8/8/8/8/RR2K3 w Ba - 0 1");
+
+        let castle = state.get_legal_moves().into_iter().find(|m| m.castle.is_some()).unwrap();
+        assert_eq!(castle.castle, Some((Position::new(0, 1), Position::new(0, 3))));
+
+        assert_eq!(
+            "r3k3/8/8/8/8/8/8/RR2K3 w Q - 0 1".to_fen().to_state().unwrap().get_castle_rook_file(Color::White, CastleMoves::QueenSide),
+            0
+        );
+        assert!("r3k3/8/8/8/8/8/8/RR2K3 w qB - 0 1".to_fen().to_state().is_err());
+        assert!("r3k3/8/8/8/8/8/8/RR2K3 w E - 0 1".to_fen().to_state().is_err());
+    }
+
+    #[test]
+    fn test_board() {
+        let placement = "r3k2r/8/8/8/8/8/8/R3K2R";
+        let board = placement.to_fen().to_board().unwrap();
+
+        assert_eq!(board.occupied().len(), 6);
+        assert_eq!(board.to_fen().to_string(), placement);
+        assert_eq!(Piece::new(Color::Black, PieceType::Knight).to_fen().to_string(), "n");
+
+        assert!("8/8/8/8/8/8/8".to_fen().to_board().is_err());
+        assert!("8/8/8/8/8/8/8/9".to_fen().to_board().is_err());
+    }
 }
@@ -1,9 +1,14 @@
 mod format;
 mod alg;
 mod fen;
+mod epd;
 mod pgn;
+mod pgn_lex;
+mod uci;
 
-pub use format::{ToPosition, ToMove, ToState};
-pub use alg::{ToAlg, AlgNotation};
-pub use fen::{ToFEN, FENotation};
-pub use pgn::{ToPGN, PGNotation};
+pub use format::{ToPosition, ToPieceType, ToPiece, ToMove, ToState};
+pub use alg::{ToAlg, ToSAN, AlgNotation};
+pub use fen::{ToFEN, ToShredderFEN, FENotation, FENMode};
+pub use epd::{ToEpd, EpdNotation, EpdRecord, EpdOperand};
+pub use pgn::{ToPGN, PGNotation, PGNGame, PGNMove, PGNResult, PGNReader};
+pub use uci::{ToUci, UciNotation};
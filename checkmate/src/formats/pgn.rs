@@ -1,13 +1,15 @@
 use std::fmt;
+use std::mem;
+use std::io::BufRead;
 
-use regex::Regex;
-use lazy_static::lazy_static;
+use readonly;
 
-use crate::formats::ToMove;
-use crate::model::{State};
+use crate::model::{State, Move, Color, EndResult};
 use crate::errors::ValidationError;
-use super::format::ToState;
-use super::alg::ToAlg;
+use super::format::{ToState, ToMove};
+use super::alg::{ToAlg, ToSAN};
+use super::fen::{ToFEN, FENMode};
+use super::pgn_lex::{Lexer, Lexeme, Token};
 
 pub trait ToPGN {
     fn to_pgn(&self) -> PGNotation;
@@ -25,6 +27,11 @@ impl PGNotation {
     pub fn new(data: impl AsRef<str>) -> Self {
         Self(data.as_ref().to_string())
     }
+
+    /// Parse the notation as a single game, keeping tags, variations and annotations.
+    pub fn to_game(self) -> Result<PGNGame, ValidationError> {
+        parse_game(&self.0, 1)
+    }
 }
 
 impl fmt::Display for PGNotation {
@@ -33,63 +40,591 @@ impl fmt::Display for PGNotation {
     }
 }
 
+/// The game termination marker ending PGN movetext.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PGNResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unfinished
+}
+
+impl PGNResult {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(PGNResult::WhiteWins),
+            "0-1" => Some(PGNResult::BlackWins),
+            "1/2-1/2" => Some(PGNResult::Draw),
+            "*" => Some(PGNResult::Unfinished),
+            _ => None
+        }
+    }
+
+    pub fn token(&self) -> &'static str {
+        match self {
+            PGNResult::WhiteWins => "1-0",
+            PGNResult::BlackWins => "0-1",
+            PGNResult::Draw => "1/2-1/2",
+            PGNResult::Unfinished => "*"
+        }
+    }
+}
+
+impl fmt::Display for PGNResult {
+    fn fmt(&self, dest: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(dest, "{}", self.token())
+    }
+}
+
+/// A move of a PGN game with its annotations. `pre_comments` come before the move, which
+/// only happens at the start of a game or variation; `comments` follow it.
+#[readonly::make]
+#[derive(Clone, Debug)]
+pub struct PGNMove {
+    pub played: Move,
+    pub nags: Vec<u8>,
+    pub pre_comments: Vec<String>,
+    pub comments: Vec<String>,
+    /// Alternatives to this move, each played from the position before it.
+    pub variations: Vec<Vec<PGNMove>>
+}
+
+/// `PGNGame` is a game as structured by PGN: tag pairs, the mainline with its variations
+/// and annotations, and the result.
+#[readonly::make]
+#[derive(Clone)]
+pub struct PGNGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PGNMove>,
+    /// Comments in a game without moves.
+    pub comments: Vec<String>,
+    pub result: PGNResult,
+    initial_state: State
+}
+
+impl PGNGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Return the position the game starts from, which is set by the FEN tag if present.
+    pub fn initial_state(&self) -> &State {
+        &self.initial_state
+    }
+
+    /// Return the position after the mainline.
+    pub fn final_state(&self) -> State {
+        let mut state = self.initial_state.clone();
+        for pgn_move in &self.moves {
+            state.make_move(&pgn_move.played);
+        }
+
+        state
+    }
+}
+
+fn located(lexeme: &Lexeme, error: ValidationError) -> ValidationError {
+    ValidationError::Located{line: lexeme.line, column: lexeme.column, error: Box::new(error)}
+}
+
+struct Parser {
+    lexemes: Vec<Lexeme>,
+    index: usize,
+    //  Where the text ends, for errors at the end of input.
+    end: Lexeme
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Lexeme> {
+        self.lexemes.get(self.index)
+    }
+
+    fn next(&mut self) -> Result<Lexeme, ValidationError> {
+        match self.lexemes.get(self.index) {
+            Some(lexeme) => {
+                self.index += 1;
+
+                Ok(lexeme.clone())
+            },
+            None => Err(located(&self.end, ValidationError::Parse{token: "end of game".to_string()}))
+        }
+    }
+
+    fn unexpected(lexeme: &Lexeme) -> ValidationError {
+        located(lexeme, ValidationError::Parse{token: lexeme.token.to_string()})
+    }
+
+    fn expect(&mut self, token: Token) -> Result<Lexeme, ValidationError> {
+        let lexeme = self.next()?;
+        match lexeme.token == token {
+            true => Ok(lexeme),
+            false => Err(Self::unexpected(&lexeme))
+        }
+    }
+
+    fn parse_tag(&mut self) -> Result<(String, String, Lexeme), ValidationError> {
+        self.expect(Token::LeftBracket)?;
+
+        let name_lexeme = self.next()?;
+        let name = match &name_lexeme.token {
+            Token::Symbol(name) => name.clone(),
+            _ => return Err(Self::unexpected(&name_lexeme))
+        };
+        let value_lexeme = self.next()?;
+        let value = match &value_lexeme.token {
+            Token::Str(value) => value.clone(),
+            _ => return Err(Self::unexpected(&value_lexeme))
+        };
+
+        self.expect(Token::RightBracket)?;
+
+        Ok((name, value, value_lexeme))
+    }
+
+    /// Parse moves from `state` until the end of the line: a result marker on the mainline,
+    /// or the closing parenthesis of a variation, which is left for the caller.
+    fn parse_line(&mut self, mut state: State, depth: usize) -> Result<(Vec<PGNMove>, Vec<String>), ValidationError> {
+        let mut moves: Vec<PGNMove> = Vec::new();
+        let mut pending_comments: Vec<String> = Vec::new();
+        let mut before_last: Option<State> = None;
+
+        while let Some(lexeme) = self.peek() {
+            let ends_line = match &lexeme.token {
+                Token::RightParen => depth > 0,
+                Token::Asterisk => depth == 0,
+                Token::Symbol(symbol) => depth == 0 && PGNResult::from_token(symbol).is_some(),
+                _ => false
+            };
+            if ends_line {
+                break;
+            }
+
+            let lexeme = self.next()?;
+            match lexeme.token.clone() {
+                Token::Period => (),
+                //  Move numbers carry no information beyond the move order.
+                Token::Symbol(symbol) if symbol.chars().all(|c| c.is_ascii_digit()) => (),
+                Token::Symbol(symbol) => {
+                    let played = symbol.to_alg().to_move(&state).map_err(|e| located(&lexeme, e))?;
+
+                    before_last = Some(state.clone());
+                    state.make_move(&played);
+
+                    moves.push(PGNMove{
+                        played,
+                        nags: Vec::new(),
+                        pre_comments: mem::take(&mut pending_comments),
+                        comments: Vec::new(),
+                        variations: Vec::new()
+                    });
+                },
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) => last.comments.push(comment),
+                    None => pending_comments.push(comment)
+                },
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(nag),
+                    None => return Err(Self::unexpected(&lexeme))
+                },
+                Token::LeftParen => {
+                    let before = match &before_last {
+                        Some(before) => before.clone(),
+                        None => return Err(Self::unexpected(&lexeme))
+                    };
+
+                    let (variation, _) = self.parse_line(before, depth + 1)?;
+                    self.expect(Token::RightParen)?;
+
+                    moves.last_mut().unwrap().variations.push(variation);
+                },
+                _ => return Err(Self::unexpected(&lexeme))
+            }
+        }
+
+        Ok((moves, pending_comments))
+    }
+}
+
+/// Parse the text of one game, numbering its lines from `first_line`.
+fn parse_game(text: &str, first_line: usize) -> Result<PGNGame, ValidationError> {
+    let lexemes: Vec<Lexeme> = Lexer::new(text, first_line).collect::<Result<_, _>>()?;
+    let end = Lexeme{
+        token: Token::Asterisk,
+        line: first_line + text.lines().count().saturating_sub(1),
+        column: text.lines().last().map_or(1, |l| l.chars().count() + 1)
+    };
+    let mut parser = Parser{lexemes, index: 0, end};
+
+    let mut tags: Vec<(String, String)> = Vec::new();
+    let mut initial_state = State::default();
+    while parser.peek().is_some_and(|l| l.token == Token::LeftBracket) {
+        let (name, value, value_lexeme) = parser.parse_tag()?;
+
+        if name == "FEN" {
+            initial_state = value.to_fen().to_state_with(FENMode::Lenient).map_err(|e| located(&value_lexeme, e))?;
+        }
+        tags.push((name, value));
+    }
+
+    let (moves, comments) = parser.parse_line(initial_state.clone(), 0)?;
+
+    let result = match parser.peek().cloned() {
+        Some(lexeme) => {
+            parser.index += 1;
+            PGNResult::from_token(&lexeme.token.to_string()).unwrap()
+        },
+        //  Tolerate a missing termination marker, falling back to the Result tag.
+        None => tags.iter().find(|(name, _)| name == "Result")
+            .and_then(|(_, value)| PGNResult::from_token(value))
+            .unwrap_or(PGNResult::Unfinished)
+    };
+
+    if let Some(lexeme) = parser.peek() {
+        return Err(Parser::unexpected(lexeme));
+    }
+
+    Ok(PGNGame{tags, moves, comments, result, initial_state})
+}
+
 impl ToState for PGNotation {
     fn to_state(self) -> Result<State, ValidationError> {
-        lazy_static! {
-            static ref TURN_RE: Regex = Regex::new(r"([0-9])+\.\s([^\s]+)\s([^\s]+)").unwrap();
+        Ok(self.to_game()?.final_state())
+    }
+}
+
+/// The tags every exported game starts with, in order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+const MAX_LINE_LEN: usize = 80;
+
+fn escape_tag(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    //  Split into words so long comments can wrap.
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words.len() {
+        0 => tokens.push("{}".to_string()),
+        count => for (i, word) in words.iter().enumerate() {
+            let open = if i == 0 { "{" } else { "" };
+            let close = if i == count - 1 { "}" } else { "" };
+
+            tokens.push(format!("{}{}{}", open, word, close));
         }
+    }
+}
 
-        let mut state = State::default();
+/// Append the movetext tokens of `moves` played from `state`. Parentheses are separate
+/// tokens here and joined to their neighbours when wrapping.
+fn push_line(tokens: &mut Vec<String>, mut state: State, moves: &[PGNMove]) {
+    let mut needs_number = true;
 
-        for captures in TURN_RE.captures_iter(&self.0) {
-            let white_move_str = &captures[2];
-            let black_move_str = &captures[3];
+    for pgn_move in moves {
+        for comment in &pgn_move.pre_comments {
+            push_comment(tokens, comment);
+        }
+
+        match (state.active_color, needs_number) {
+            (Color::White, _) => tokens.push(format!("{}.", state.fullmove_number)),
+            (Color::Black, true) => tokens.push(format!("{}...", state.fullmove_number)),
+            (Color::Black, false) => ()
+        }
+        tokens.push(pgn_move.played.to_san(&state).to_string());
+        for nag in &pgn_move.nags {
+            tokens.push(format!("${}", nag));
+        }
+        needs_number = false;
 
-            state = state.next_for_move(&white_move_str.to_alg().to_move(&state)?);
-            state = state.next_for_move(&black_move_str.to_alg().to_move(&state)?);
+        for comment in &pgn_move.comments {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+        for variation in &pgn_move.variations {
+            tokens.push("(".to_string());
+            push_line(tokens, state.clone(), variation);
+            tokens.push(")".to_string());
+            needs_number = true;
         }
 
-        Ok(state)
+        state.make_move(&pgn_move.played);
     }
 }
 
-impl ToPGN for State {
+fn wrap_tokens(tokens: Vec<String>) -> Vec<String> {
+    let mut joined: Vec<String> = Vec::new();
+    let mut opening = String::new();
+    for token in tokens {
+        match token.as_str() {
+            "(" => opening.push('('),
+            ")" => match joined.last_mut() {
+                Some(last) if opening.is_empty() => last.push(')'),
+                _ => {
+                    joined.push(format!("{})", opening));
+                    opening.clear();
+                }
+            },
+            _ => joined.push(format!("{}{}", mem::take(&mut opening), token))
+        }
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for token in joined {
+        if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LEN {
+            lines.push(mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    lines.push(line);
+
+    lines
+}
+
+impl From<&EndResult> for PGNResult {
+    fn from(result: &EndResult) -> Self {
+        match result.winner {
+            Some(Color::White) => PGNResult::WhiteWins,
+            Some(Color::Black) => PGNResult::BlackWins,
+            None => PGNResult::Draw
+        }
+    }
+}
+
+impl PGNMove {
+    pub fn new(played: Move) -> Self {
+        Self{played, nags: Vec::new(), pre_comments: Vec::new(), comments: Vec::new(), variations: Vec::new()}
+    }
+
+    pub fn with_nag(mut self, nag: u8) -> Self {
+        self.nags.push(nag);
+
+        self
+    }
+
+    pub fn with_comment(mut self, comment: impl AsRef<str>) -> Self {
+        self.comments.push(comment.as_ref().to_string());
+
+        self
+    }
+
+    /// Add an alternative line, played from the position before this move.
+    pub fn with_variation(mut self, variation: Vec<PGNMove>) -> Self {
+        self.variations.push(variation);
+
+        self
+    }
+}
+
+impl PGNGame {
+    /// Start an empty game record from `initial_state`.
+    pub fn new(initial_state: State) -> Self {
+        Self{
+            tags: Vec::new(),
+            moves: Vec::new(),
+            comments: Vec::new(),
+            result: PGNResult::Unfinished,
+            initial_state
+        }
+    }
+
+    /// Record the game played to reach `state`, with its result if it has ended.
+    pub fn from_state(state: &State) -> Self {
+        let mut game = Self::new(state.initial_state());
+        game.moves = state.move_history.iter().map(|m| PGNMove::new(m.clone())).collect();
+        if let Some(result) = state.check_result() {
+            game.result = PGNResult::from(&result);
+        }
+
+        game
+    }
+
+    /// Set a tag, replacing any existing value. The Result tag is written from
+    /// [`PGNGame::result`] instead.
+    pub fn set_tag(&mut self, name: impl AsRef<str>, value: impl AsRef<str>) {
+        let (name, value) = (name.as_ref().to_string(), value.as_ref().to_string());
+
+        match self.tags.iter_mut().find(|(n, _)| *n == name) {
+            Some(tag) => tag.1 = value,
+            None => self.tags.push((name, value))
+        }
+    }
+
+    pub fn set_result(&mut self, result: PGNResult) {
+        self.result = result;
+    }
+
+    pub fn push_move(&mut self, pgn_move: PGNMove) {
+        self.moves.push(pgn_move);
+    }
+}
+
+impl ToPGN for PGNGame {
+    /// Write the game in PGN export format: the Seven Tag Roster with "?" for unknown
+    /// values, other tags, SetUp and FEN for a non-standard start, then movetext wrapped
+    /// at 80 columns and ending with the result.
     fn to_pgn(&self) -> PGNotation {
-        let mut turn_strs: Vec<String> = Vec::new();
+        let mut tag_strs: Vec<String> = Vec::new();
 
-        let mut moves_iter = self.move_history.iter();
-        loop {
-            let white_move_str = match moves_iter.next() {
-                Some(m) => m.to_alg().to_string(),
-                None => break,
+        for name in SEVEN_TAG_ROSTER {
+            let value = match (name, self.tag(name)) {
+                ("Result", _) => self.result.token(),
+                (_, Some(value)) => value,
+                ("Date", None) => "????.??.??",
+                (_, None) => "?"
             };
-            
-            let mut ended = false;
-            let black_move_str = match moves_iter.next() {
-                Some(m) => m.to_alg().to_string(),
-                None => {
-                    ended = true;
-                    "...".to_string()
+
+            tag_strs.push(format!("[{} \"{}\"]", name, escape_tag(value)));
+        }
+
+        let initial_fen = self.initial_state.to_fen().to_string();
+        let standard_start = initial_fen == State::default().to_fen().to_string();
+        for (name, value) in &self.tags {
+            if SEVEN_TAG_ROSTER.contains(&name.as_str()) || name == "SetUp" || name == "FEN" {
+                continue;
+            }
+
+            tag_strs.push(format!("[{} \"{}\"]", name, escape_tag(value)));
+        }
+        if !standard_start {
+            tag_strs.push("[SetUp \"1\"]".to_string());
+            tag_strs.push(format!("[FEN \"{}\"]", initial_fen));
+        }
+
+        let mut tokens: Vec<String> = Vec::new();
+        for comment in &self.comments {
+            push_comment(&mut tokens, comment);
+        }
+        push_line(&mut tokens, self.initial_state.clone(), &self.moves);
+        tokens.push(self.result.token().to_string());
+
+        format!("{}\n\n{}\n", tag_strs.join("\n"), wrap_tokens(tokens).join("\n")).to_pgn()
+    }
+}
+
+impl ToPGN for State {
+    /// Write the game played to reach this state, with unknown tags.
+    fn to_pgn(&self) -> PGNotation {
+        PGNGame::from_state(self).to_pgn()
+    }
+}
+
+/// `PGNReader` reads the games of a PGN database one at a time, holding only the game
+/// being parsed in memory.
+///
+/// A malformed game is reported as a [`ValidationError::InGame`] carrying its index and
+/// the line of the problem, and reading continues with the next game.
+pub struct PGNReader<R: BufRead> {
+    source: R,
+    line_number: usize,
+    game_index: usize,
+    //  The first line of the next game, read while looking for the end of the last one.
+    lookahead: Option<(String, usize)>,
+    failed: bool
+}
+
+impl<R: BufRead> PGNReader<R> {
+    pub fn new(source: R) -> Self {
+        Self{source, line_number: 0, game_index: 0, lookahead: None, failed: false}
+    }
+
+    fn read_line(&mut self) -> Result<Option<(String, usize)>, ValidationError> {
+        if let Some(lookahead) = self.lookahead.take() {
+            return Ok(Some(lookahead));
+        }
+
+        let mut line = String::new();
+        match self.source.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.line_number += 1;
+
+                Ok(Some((line, self.line_number)))
+            },
+            Err(e) => Err(ValidationError::Io{message: e.to_string()})
+        }
+    }
+
+    /// Collect the text of the next game and the number of its first line. A game ends
+    /// with a line ending in a termination marker, or before the tags of the next game.
+    fn read_game(&mut self) -> Result<Option<(String, usize)>, ValidationError> {
+        let mut text = String::new();
+        let mut first_line: Option<usize> = None;
+        let (mut in_movetext, mut in_comment) = (false, false);
+
+        while let Some((line, line_number)) = self.read_line()? {
+            let trimmed = line.trim();
+            if trimmed.is_empty() && first_line.is_none() {
+                continue;
+            }
+            if in_movetext && !in_comment && trimmed.starts_with('[') {
+                self.lookahead = Some((line, line_number));
+                break;
+            }
+
+            first_line.get_or_insert(line_number);
+            if !in_comment && !trimmed.is_empty() && !trimmed.starts_with('[') && !trimmed.starts_with('%') {
+                in_movetext = true;
+            }
+            //  Tag values and escaped lines may hold braces that don't open comments, and
+            //  only the text outside comments can end the game.
+            let mut uncommented = String::new();
+            let scan_comments = in_comment || !(trimmed.starts_with('[') || trimmed.starts_with('%'));
+            if scan_comments {
+                for c in trimmed.chars() {
+                    match (in_comment, c) {
+                        (true, '}') => {
+                            in_comment = false;
+                            uncommented.push(' ');
+                        },
+                        (false, '{') => in_comment = true,
+                        (false, ';') => break,
+                        (false, c) => uncommented.push(c),
+                        _ => ()
+                    }
                 }
-            };
+            }
+            text.push_str(&line);
 
-            turn_strs.push(format!(
-                "{}. {} {}",
-                turn_strs.len() + 1, white_move_str, black_move_str 
-            ));
-            
-            if ended {
+            let last_word = uncommented.split_whitespace().last().unwrap_or("");
+            if in_movetext && !in_comment && PGNResult::from_token(last_word).is_some() {
                 break;
             }
         }
 
-        turn_strs.join(" ").to_pgn()
+        Ok(first_line.map(|first_line| (text, first_line)))
+    }
+}
+
+impl<R: BufRead> Iterator for PGNReader<R> {
+    type Item = Result<PGNGame, ValidationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let (text, first_line) = match self.read_game() {
+            Ok(game) => game?,
+            Err(e) => {
+                self.failed = true;
+
+                return Some(Err(e));
+            }
+        };
+
+        let index = self.game_index;
+        self.game_index += 1;
+
+        Some(parse_game(&text, first_line).map_err(|e| ValidationError::InGame{index, error: Box::new(e)}))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::{Position, Piece, Color, PieceType};
+    use crate::model::{Position, Piece, PieceType};
     use super::*;
 
     #[test]
@@ -101,4 +636,230 @@ mod tests {
         assert_eq!(state.board[Position::new(2, 5)].as_ref().unwrap(), &Piece::new(Color::White, PieceType::Knight));
         assert_eq!(state.board[Position::new(5, 2)].as_ref().unwrap(), &Piece::new(Color::Black, PieceType::Knight));
     }
+
+    const ANNOTATED: &str = r#"[Event "Casual game"]
+[Site "?"]
+[White "Anderssen, A."]
+[Black "Kieseritzky, L."]
+[Result "1-0"]
+
+{Scholar's mate, with a detour.} 1. e4 e5 2. Qh5 $6 Nc6 (2... g6 3. Qxe5+ Qe7
+(3... Ne7?? 4. Qxh8) 4. Qxh8) 3. Bc4 ; threatening mate
+3... Nf6?? 4. Qxf7# 1-0"#;
+
+    #[test]
+    fn test_parse_game() {
+        let game = ANNOTATED.to_pgn().to_game().unwrap();
+
+        assert_eq!(game.tag("White"), Some("Anderssen, A."));
+        assert_eq!(game.tags.len(), 5);
+        assert_eq!(game.result, PGNResult::WhiteWins);
+
+        let sans: Vec<String> = game.moves.iter().map(|m| m.played.to_alg().to_string()).collect();
+        assert_eq!(sans, vec!["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7"]);
+
+        assert_eq!(game.moves[0].pre_comments, vec!["Scholar's mate, with a detour.".to_string()]);
+        assert_eq!(game.moves[2].nags, vec![6]);
+        assert_eq!(game.moves[4].comments, vec!["threatening mate".to_string()]);
+        assert_eq!(game.moves[5].nags, vec![4]);
+
+        //  The variation replaces 2... Nc6 and nests its own alternative to 3... Qe7.
+        let variation = &game.moves[3].variations[0];
+        assert_eq!(variation.len(), 4);
+        assert_eq!(variation[0].played.to_alg().to_string(), "g6");
+        assert_eq!(variation[2].variations[0][1].played.to_alg().to_string(), "Qxh8");
+
+        let state = game.final_state();
+        assert!(state.is_check_against(Color::Black));
+        assert!(state.get_legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_parse_game_variants() {
+        //  Ending on White's move, without a termination marker.
+        let game = "1. e4 e5 2. Nf3".to_pgn().to_game().unwrap();
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.result, PGNResult::Unfinished);
+
+        let game = "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n[SetUp \"1\"]\n1... Kd7 2. e4 *".to_pgn().to_game().unwrap();
+        assert_eq!(game.initial_state().active_color, Color::Black);
+        assert_eq!(game.final_state().fullmove_number, 2);
+
+        let game = "{Nothing happened.} 1/2-1/2".to_pgn().to_game().unwrap();
+        assert!(game.moves.is_empty());
+        assert_eq!(game.comments, vec!["Nothing happened.".to_string()]);
+        assert_eq!(game.result, PGNResult::Draw);
+    }
+
+    fn location_of(text: &str) -> (usize, usize) {
+        match text.to_pgn().to_game().err().unwrap() {
+            ValidationError::Located{line, column, ..} => (line, column),
+            other => panic!("unlocated error {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(location_of("[Event \"?\"]\n\n1. e4 e5 2. Ke3"), (3, 13));
+        assert_eq!(location_of("1. e4 (1. d4 d5"), (1, 16));
+        assert_eq!(location_of("1. e4 e5\n2. Nf3 {unclosed"), (2, 8));
+        assert_eq!(location_of("[Event ?]"), (1, 8));
+        assert_eq!(location_of("1. e4 e5 1-0 2. Nf3"), (1, 14));
+        assert_eq!(location_of("(1. e4)"), (1, 1));
+
+        let error = "1. e4 e5 2. N3".to_pgn().to_game().err().unwrap();
+        assert_eq!(error.to_string(), "1:13: invalid format: N3");
+    }
+
+    #[test]
+    fn test_write_game() {
+        let mut state = State::default();
+        for move_str in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"] {
+            state.make_move(&move_str.to_alg().to_move(&state).unwrap());
+        }
+
+        let mut game = PGNGame::from_state(&state);
+        game.set_tag("White", "Player \"One\"");
+        game.set_tag("Annotator", "checkmate");
+
+        assert_eq!(game.to_pgn().to_string(), concat!(
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n",
+            "[White \"Player \\\"One\\\"\"]\n[Black \"?\"]\n[Result \"1-0\"]\n[Annotator \"checkmate\"]\n",
+            "\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"
+        ));
+    }
+
+    #[test]
+    fn test_write_setup() {
+        let mut state = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1".to_fen().to_state().unwrap();
+        for move_str in ["Kd7", "e4"] {
+            state.make_move(&move_str.to_alg().to_move(&state).unwrap());
+        }
+
+        let pgn_str = state.to_pgn().to_string();
+        assert!(pgn_str.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n"));
+        assert!(pgn_str.ends_with("\n\n1... Kd7 2. e4 *\n"));
+
+        let reparsed = pgn_str.to_pgn().to_game().unwrap();
+        assert_eq!(reparsed.final_state().to_fen().to_string(), state.to_fen().to_string());
+    }
+
+    fn sans_of(moves: &[PGNMove]) -> Vec<String> {
+        moves.iter().map(|m| m.played.to_alg().to_string()).collect()
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let game = ANNOTATED.to_pgn().to_game().unwrap();
+        let pgn_str = game.to_pgn().to_string();
+
+        assert!(pgn_str.lines().all(|l| l.len() <= MAX_LINE_LEN));
+        assert!(pgn_str.replace('\n', " ").contains("2. Qh5 $6 Nc6 (2... g6 3. Qxe5+ Qe7 (3... Ne7 $4 4. Qxh8) 4. Qxh8) 3. Bc4"));
+
+        let reparsed = pgn_str.to_pgn().to_game().unwrap();
+        assert_eq!(reparsed.tags.len(), game.tags.len() + 2);
+        assert_eq!(reparsed.result, game.result);
+        assert_eq!(sans_of(&reparsed.moves), sans_of(&game.moves));
+        assert_eq!(sans_of(&reparsed.moves[3].variations[0]), sans_of(&game.moves[3].variations[0]));
+        assert_eq!(reparsed.moves[0].pre_comments, game.moves[0].pre_comments);
+        assert_eq!(reparsed.moves[4].comments, game.moves[4].comments);
+    }
+
+    #[test]
+    fn test_write_wrapping() {
+        let mut state = State::default();
+        let mut game = PGNGame::new(state.clone());
+        for move_str in ["Nf3", "Nf6", "Ng1", "Ng8"].iter().cycle().take(40) {
+            let played = move_str.to_alg().to_move(&state).unwrap();
+            state.make_move(&played);
+
+            game.push_move(PGNMove::new(played).with_comment("a long comment that has to be split across lines"));
+        }
+        game.set_result(PGNResult::Draw);
+
+        let pgn_str = game.to_pgn().to_string();
+        assert!(pgn_str.lines().all(|l| l.len() <= MAX_LINE_LEN));
+        assert!(pgn_str.lines().filter(|l| l.len() > 70).count() > 10);
+        assert!(pgn_str.ends_with(" 1/2-1/2\n"));
+
+        let reparsed = pgn_str.to_pgn().to_game().unwrap();
+        assert_eq!(reparsed.moves.len(), 40);
+        assert_eq!(reparsed.moves[39].comments, vec!["a long comment that has to be split across lines".to_string()]);
+    }
+
+    const DATABASE: &str = r#"[Event "First"]
+[Result "1-0"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+[Event "Second"]
+[Result "*"]
+
+1. e4 e5 2. Ke3 *
+[Event "Third"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 {A comment
+[that looks like a tag]} Qh4# 0-1
+1. d4 d5 1/2-1/2
+"#;
+
+    #[test]
+    fn test_read_database() {
+        let games: Vec<Result<PGNGame, ValidationError>> = PGNReader::new(DATABASE.as_bytes()).collect();
+        assert_eq!(games.len(), 4);
+
+        assert_eq!(games[0].as_ref().unwrap().tag("Event"), Some("First"));
+        assert_eq!(games[0].as_ref().unwrap().moves.len(), 7);
+
+        match games[1].as_ref().err().unwrap() {
+            ValidationError::InGame{index, error} => {
+                assert_eq!(*index, 1);
+                assert!(matches!(**error, ValidationError::Located{line: 9, column: 13, ..}));
+            },
+            other => panic!("unexpected error {:?}", other)
+        }
+
+        let third = games[2].as_ref().unwrap();
+        assert_eq!(third.tag("Event"), Some("Third"));
+        assert_eq!(third.result, PGNResult::BlackWins);
+        assert_eq!(third.moves[2].comments, vec!["A comment [that looks like a tag]".to_string()]);
+
+        let fourth = games[3].as_ref().unwrap();
+        assert!(fourth.tags.is_empty());
+        assert_eq!(fourth.result, PGNResult::Draw);
+    }
+
+    #[test]
+    fn test_read_many() {
+        let game_str = "[Event \"?\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n\n";
+        let database = game_str.repeat(500);
+
+        let mut reader = PGNReader::new(database.as_bytes());
+        assert_eq!(reader.by_ref().filter(|g| g.is_ok()).count(), 500);
+        assert_eq!(reader.line_number, 2000);
+    }
+
+    #[test]
+    fn test_read_brace_in_tag() {
+        let database = "[Event \"First\"]\n[Annotator \"x {y\"]\n\n1. e4 e5 1-0\n\n[Event \"Second\"]\n\n1. d4 d5 0-1\n";
+        let games: Vec<PGNGame> = PGNReader::new(database.as_bytes()).map(|g| g.unwrap()).collect();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("Annotator"), Some("x {y"));
+        assert_eq!(games[0].moves.len(), 2);
+        assert_eq!(games[1].tag("Event"), Some("Second"));
+        assert_eq!(games[1].result, PGNResult::BlackWins);
+    }
+
+    #[test]
+    fn test_read_commented_result() {
+        let database = "[Event \"First\"]\n\n1. e4 e5 ; white claims 1-0\n2. Nf3 {black resigns? 1-0}\nNc6 1-0\n\n[Event \"Second\"]\n\n1. d4 d5 0-1\n";
+        let games: Vec<PGNGame> = PGNReader::new(database.as_bytes()).map(|g| g.unwrap()).collect();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves.len(), 4);
+        assert_eq!(games[0].result, PGNResult::WhiteWins);
+        assert_eq!(games[1].tag("Event"), Some("Second"));
+    }
 }
@@ -0,0 +1,226 @@
+use std::fmt;
+
+use crate::errors::ValidationError;
+
+//  Tokenizer for PGN import format. Tracks the line and column of each token so parse
+//  errors can point back into the source text.
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Token {
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    Period,
+    Asterisk,
+    Str(String),
+    /// Tag names, move numbers, SAN moves and result markers.
+    Symbol(String),
+    Comment(String),
+    Nag(u8)
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, dest: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LeftBracket => write!(dest, "["),
+            Token::RightBracket => write!(dest, "]"),
+            Token::LeftParen => write!(dest, "("),
+            Token::RightParen => write!(dest, ")"),
+            Token::Period => write!(dest, "."),
+            Token::Asterisk => write!(dest, "*"),
+            Token::Str(value) => write!(dest, "\"{}\"", value),
+            Token::Symbol(value) => write!(dest, "{}", value),
+            Token::Comment(value) => write!(dest, "{{{}}}", value),
+            Token::Nag(nag) => write!(dest, "${}", nag)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct Lexeme {
+    pub token: Token,
+    pub line: usize,
+    pub column: usize
+}
+
+pub(super) struct Lexer<'t> {
+    chars: std::iter::Peekable<std::str::Chars<'t>>,
+    line: usize,
+    column: usize
+}
+
+impl<'t> Lexer<'t> {
+    /// Tokenize `text`, numbering lines from `first_line`.
+    pub fn new(text: &'t str, first_line: usize) -> Self {
+        Self{chars: text.chars().peekable(), line: first_line, column: 1}
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let next = self.chars.next()?;
+        match next {
+            '\n' => {
+                self.line += 1;
+                self.column = 1;
+            },
+            _ => self.column += 1
+        }
+
+        Some(next)
+    }
+
+    fn error(&self, token: impl AsRef<str>, line: usize, column: usize) -> ValidationError {
+        ValidationError::Located{
+            line, column,
+            error: Box::new(ValidationError::Parse{token: token.as_ref().to_string()})
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(next) = self.chars.peek() {
+            if !predicate(*next) {
+                break;
+            }
+
+            taken.push(*next);
+            self.bump();
+        }
+
+        taken
+    }
+
+    fn next_lexeme(&mut self) -> Result<Option<Lexeme>, ValidationError> {
+        loop {
+            //  Escaped lines start with '%' in the first column and are ignored.
+            if self.column == 1 && self.chars.peek() == Some(&'%') {
+                self.take_while(|c| c != '\n');
+                continue;
+            }
+
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                },
+                Some(_) => break,
+                None => return Ok(None)
+            }
+        }
+
+        let (line, column) = (self.line, self.column);
+        let next = self.bump().unwrap();
+
+        let token = match next {
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '.' => Token::Period,
+            '*' => Token::Asterisk,
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\\') => match self.bump() {
+                            Some(escaped) => value.push(escaped),
+                            None => return Err(self.error(value, line, column))
+                        },
+                        Some(c) => value.push(c),
+                        None => return Err(self.error(value, line, column))
+                    }
+                }
+
+                Token::Str(value)
+            },
+            '{' => {
+                let mut value = String::new();
+                loop {
+                    match self.bump() {
+                        Some('}') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(self.error(format!("{{{}", value), line, column))
+                    }
+                }
+
+                //  Line breaks within comments are insignificant, so export can wrap them.
+                Token::Comment(value.split_whitespace().collect::<Vec<&str>>().join(" "))
+            },
+            ';' => Token::Comment(self.take_while(|c| c != '\n').trim().to_string()),
+            '$' => {
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                match digits.parse::<u8>() {
+                    Ok(nag) => Token::Nag(nag),
+                    Err(_) => return Err(self.error(format!("${}", digits), line, column))
+                }
+            },
+            //  Move suffix annotations are shorthand for the first six NAGs.
+            '!' | '?' => {
+                let suffix = format!("{}{}", next, self.take_while(|c| c == '!' || c == '?'));
+                match suffix.as_str() {
+                    "!" => Token::Nag(1),
+                    "?" => Token::Nag(2),
+                    "!!" => Token::Nag(3),
+                    "??" => Token::Nag(4),
+                    "!?" => Token::Nag(5),
+                    "?!" => Token::Nag(6),
+                    _ => return Err(self.error(suffix, line, column))
+                }
+            },
+            c if c.is_ascii_alphanumeric() => {
+                let rest = self.take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c));
+
+                Token::Symbol(format!("{}{}", c, rest))
+            },
+            other => return Err(self.error(other.to_string(), line, column))
+        };
+
+        Ok(Some(Lexeme{token, line, column}))
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Lexeme, ValidationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_lexeme().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens_of(text: &str) -> Vec<Token> {
+        Lexer::new(text, 1).map(|l| l.unwrap().token).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            tokens_of("[Event \"A \\\"quoted\\\" name\"]\n1. e4 {best by test} e5!? 2... $14 (; line\n) 1/2-1/2"),
+            vec![
+                Token::LeftBracket, Token::Symbol("Event".to_string()), Token::Str("A \"quoted\" name".to_string()), Token::RightBracket,
+                Token::Symbol("1".to_string()), Token::Period, Token::Symbol("e4".to_string()),
+                Token::Comment("best by test".to_string()), Token::Symbol("e5".to_string()), Token::Nag(5),
+                Token::Symbol("2".to_string()), Token::Period, Token::Period, Token::Period, Token::Nag(14),
+                Token::LeftParen, Token::Comment("line".to_string()), Token::RightParen,
+                Token::Symbol("1/2-1/2".to_string())
+            ]
+        );
+        assert_eq!(tokens_of("% escaped\n*"), vec![Token::Asterisk]);
+    }
+
+    #[test]
+    fn test_positions() {
+        let lexemes: Vec<Lexeme> = Lexer::new("1. e4\n  Nf6", 10).map(|l| l.unwrap()).collect();
+
+        assert_eq!((lexemes[2].line, lexemes[2].column), (10, 4));
+        assert_eq!((lexemes[3].line, lexemes[3].column), (11, 3));
+
+        assert_eq!(
+            Lexer::new("1. e4 {open", 1).last().unwrap().err(),
+            Some(ValidationError::Located{line: 1, column: 7, error: Box::new(ValidationError::Parse{token: "{open".to_string()})})
+        );
+    }
+}
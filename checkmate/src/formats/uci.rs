@@ -1,11 +1,141 @@
-use super::super::model::{Game, Move};
-use super::super::errors::ValidationError;
-use super::format::{GameFormat, MoveFormat};
+use std::fmt;
 
-pub struct UCINotationFormat;
+use regex::Regex;
+use lazy_static::lazy_static;
 
-impl MoveFormat for UCINotationFormat {
-    fn parse_move(&self, game: &Game, data: impl AsRef<str>) -> Result<&Move, ValidationError> {
-        
+use crate::model::{State, Move, Position};
+use crate::errors::ValidationError;
+use super::format::{ToMove, ToPosition, ToPieceType};
+use super::alg::ToAlg;
+
+pub trait ToUci {
+    fn to_uci(&self) -> UciNotation;
+}
+
+impl<T: AsRef<str>> ToUci for T {
+    fn to_uci(&self) -> UciNotation {
+        UciNotation::new(self)
+    }
+}
+
+/// `UciNotation` is the long algebraic move format of the UCI engine protocol: origin and
+/// destination squares, then a lowercase promotion piece ("e2e4", "e7e8q"). Castles are
+/// written as the king's move ("e1g1").
+pub struct UciNotation(String);
+
+impl UciNotation {
+    pub fn new(data: impl AsRef<str>) -> Self {
+        Self(data.as_ref().to_string())
+    }
+}
+
+impl fmt::Display for UciNotation {
+    fn fmt(&self, dest: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(dest, "{}", self.0)
+    }
+}
+
+impl ToMove for UciNotation {
+    fn to_move(self, state: &State) -> Result<Move, ValidationError> {
+        lazy_static! {
+            static ref MOVE_RE: Regex = Regex::new(r"^([a-h][1-8])([a-h][1-8])([nbrq]?)$").unwrap();
+        }
+
+        let move_str = &self.0;
+        let captures = match MOVE_RE.captures(move_str) {
+            Some(m) => m,
+            None => return Err(ValidationError::Parse{token: move_str.to_owned()}),
+        };
+
+        let from_str = &captures[1];
+        let to_str = &captures[2];
+        let promotion_str = &captures[3];
+
+        let from_position = from_str.to_alg().to_position()?;
+        let to_position = to_str.to_alg().to_position()?;
+        let promotion = match promotion_str {
+            "" => None,
+            type_str => Some(type_str.to_uppercase().to_alg().to_piece_type()?)
+        };
+
+        state.get_legal_moves_from(&from_position).into_iter()
+            .find(|m| uci_destination(m) == &to_position && m.promotion == promotion)
+            .ok_or(ValidationError::InvalidState{token: move_str.to_owned()})
+    }
+}
+
+/// Return the square UCI names as the destination of `uci_move`. Castles where the king
+/// doesn't move two files, which only arise in Chess960, are written as the king taking
+/// its own rook so they can't be mistaken for a king step.
+fn uci_destination(uci_move: &Move) -> &Position {
+    match &uci_move.castle {
+        Some((rook_from, _)) if uci_move.from.file.abs_diff(uci_move.to.file) != 2 => rook_from,
+        _ => &uci_move.to
+    }
+}
+
+impl ToUci for Move {
+    fn to_uci(&self) -> UciNotation {
+        let promotion_str = match self.promotion {
+            Some(promotion) => promotion.to_alg().to_string().to_lowercase(),
+            None => "".to_string()
+        };
+
+        format!("{}{}{}", self.from.to_alg(), uci_destination(self).to_alg(), promotion_str).to_uci()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Position, PieceType};
+    use crate::formats::{ToFEN, ToState};
+
+    #[test]
+    fn test_parse_move() {
+        let state = State::default();
+
+        let push_kings_pawn = "e2e4".to_uci().to_move(&state).unwrap();
+        assert_eq!(push_kings_pawn.from, Position::new(1, 4));
+        assert_eq!(push_kings_pawn.to, Position::new(3, 4));
+
+        assert!("e2e5".to_uci().to_move(&state).is_err());
+        assert!(matches!("e2-e4".to_uci().to_move(&state), Err(ValidationError::Parse{..})));
+
+        let castles = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_fen().to_state().unwrap();
+        assert!("e1g1".to_uci().to_move(&castles).unwrap().castle.is_some());
+        assert!("e1c1".to_uci().to_move(&castles).unwrap().castle.is_some());
+
+        //  The king steps to g1 either way, so the Chess960 castle is written king takes rook.
+        let chess960 = "r4k1r/8/8/8/8/8/8/R4K1R w KQkq - 0 1".to_fen().to_state().unwrap();
+        assert!("f1g1".to_uci().to_move(&chess960).unwrap().castle.is_none());
+        assert!("f1h1".to_uci().to_move(&chess960).unwrap().castle.is_some());
+        assert!("f1a1".to_uci().to_move(&chess960).unwrap().castle.is_some());
+
+        let promotion = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        assert_eq!("e7e8q".to_uci().to_move(&promotion).unwrap().promotion, Some(PieceType::Queen));
+        assert_eq!("e7e8n".to_uci().to_move(&promotion).unwrap().promotion, Some(PieceType::Knight));
+        assert!("e7e8".to_uci().to_move(&promotion).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r4k1r/8/8/8/8/8/8/R4K1R w KQkq - 0 1"
+        ];
+
+        for fen in fens {
+            let state = fen.to_fen().to_state().unwrap();
+            for legal in state.get_legal_moves() {
+                let uci_str = legal.to_uci().to_string();
+                let parsed = uci_str.to_uci().to_move(&state).unwrap();
+
+                assert_eq!(parsed.to_uci().to_string(), uci_str);
+                assert_eq!((&parsed.from, &parsed.to, parsed.promotion), (&legal.from, &legal.to, legal.promotion));
+            }
+        }
     }
 }
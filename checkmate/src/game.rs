@@ -28,6 +28,6 @@ impl Game {
         let next_move = &agent.get_move_for_model(&state).await;
 
         let mut state_lock = self.state.lock().await;
-        *state_lock = state_lock.next_for_move(next_move);
+        state_lock.make_move(next_move);
     }
 }
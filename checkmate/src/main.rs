@@ -1,9 +1,8 @@
 use std::io;
 use std::env;
+use std::process;
 
-use tokio;
-use fern;
-use log::info;
+use log::{info, error};
 
 use checkmate::runtimes;
 
@@ -28,5 +27,8 @@ fn main() {
 
     let server = runtimes::create_api();
 
-    runtime.block_on(server.launch());
+    if let Err(launch_error) = runtime.block_on(server.launch()) {
+        error!("checkmate host failed to launch: {}", launch_error);
+        process::exit(1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,318 @@
+use lazy_static::lazy_static;
+
+use super::color::Color;
+use super::bitboard::Bitboard;
+
+//  Attack sets for every piece type, addressed by square index.
+//
+//  Leaper attacks (knight, king, pawn) are fixed per square and computed at compile
+//  time. Sliding attacks depend on board occupancy and use magic bitboards: the relevant
+//  blockers for a square are multiplied by a magic number and shifted down to a dense
+//  index into a table of precomputed attack sets. The magic numbers were found by
+//  random search and are checked against the ray walk in the tests below.
+
+const KNIGHT_STEPS: [(i32, i32); 8] = [(2, 1), (2, -1), (-2, 1), (-2, -1), (1, 2), (1, -2), (-1, 2), (-1, -2)];
+const KING_STEPS: [(i32, i32); 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0x10102002004a1420, 0x8020040400584008, 0x10510800811201c8, 0x5204042080000088,
+    0x2204106880000002, 0x1401042004000000, 0x0400880410042004, 0x0028208200a02020,
+    0x1500241990010e00, 0x8001200182020a40, 0x40004101030b0000, 0x8002041042000100,
+    0x4010011041020038, 0x0000010421044000, 0x1500210808020a00, 0x8000088400880520,
+    0x0405004010040100, 0x1005823210040108, 0x2708008102040011, 0x4048200404009100,
+    0x0018104101400024, 0x0003000601190101, 0x8004803108491000, 0x8014241200820800,
+    0x0006e080100c3040, 0x0501044a11041800, 0x9020300008004045, 0x0894080000220040,
+    0x1001010083104000, 0x5004030040900080, 0x000400422c012400, 0x0002128698404812,
+    0x1010108404900440, 0x0928021182084100, 0x2006080409020024, 0x1010202020180080,
+    0xa010008200202200, 0x2098015100019004, 0x0002041440810811, 0x802a02020000b098,
+    0x0009015090004060, 0x4000821082081001, 0x0100210040420800, 0x0800004010488a00,
+    0x2000081104004040, 0x4c8e029015000082, 0x0420340322224842, 0x1298260043400210,
+    0x0000822802400008, 0x00008a0101600000, 0x3040003412080021, 0x3040290220884800,
+    0x4a1500401041004a, 0x8010200282020781, 0x0020203142209091, 0x0070300600902110,
+    0x0040808800b62048, 0x0000810400c44420, 0x00080400440c0441, 0x8340080020840411,
+    0x0000000104208200, 0x0000800810d00080, 0x0400530411080200, 0x4040702400932244
+];
+
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080004008801020, 0x0840092002c03000, 0x1900200010400900, 0x0880100008000480,
+    0x4200100420080200, 0x8100020100080400, 0x0200040110886200, 0x0200008040220411,
+    0x0404800084400220, 0x0000401000402000, 0x0086001081220440, 0x0408800800100280,
+    0x000a001201040820, 0x8848800200840080, 0x4001000100040200, 0x0442000102105084,
+    0x9080010020804100, 0x0040404000201009, 0x0000808010002009, 0x2200090021d00100,
+    0x0008008008040080, 0x0004004002010040, 0x0011040008015042, 0x00000a0001768104,
+    0x0000800080204009, 0x2010004140002001, 0x9800200280100080, 0x1000100080080080,
+    0x0442000a00049020, 0x2100040080020080, 0x0800120400900148, 0x0010040a00128541,
+    0x2800804000800030, 0x1010002000400041, 0x4000200011004100, 0x0610008410800800,
+    0x0400802402800800, 0xc100020080800400, 0x0002000802000401, 0x0182085882000401,
+    0x0220204000808000, 0x2860100040024022, 0x0001002004110040, 0x99101042000a0020,
+    0x0004080004008080, 0x0010040002008080, 0x2012004881020004, 0x8300842444820011,
+    0x0088403882010200, 0x0820400080210100, 0x0110910040a00300, 0x0801100280080480,
+    0x0242009008200600, 0x1002000489500200, 0x0040800200010080, 0x0091800041000080,
+    0x0000209300488001, 0x04c1002414824001, 0x020020000b001041, 0x7000100004200901,
+    0x8002002004100802, 0x30010002084c0007, 0x0888221800813004, 0x4000002840840112
+];
+
+const fn leaper_table(steps: &[(i32, i32)]) -> [u64; 64] {
+    let mut table = [0u64; 64];
+
+    let mut square = 0;
+    while square < 64 {
+        let (rank, file) = ((square / 8) as i32, (square % 8) as i32);
+
+        let mut i = 0;
+        while i < steps.len() {
+            let (to_rank, to_file) = (rank + steps[i].0, file + steps[i].1);
+            if to_rank >= 0 && to_rank < 8 && to_file >= 0 && to_file < 8 {
+                table[square] |= 1 << (to_rank * 8 + to_file);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+
+    table
+}
+
+static KNIGHT_ATTACKS: [u64; 64] = leaper_table(&KNIGHT_STEPS);
+static KING_ATTACKS: [u64; 64] = leaper_table(&KING_STEPS);
+static PAWN_ATTACKS: [[u64; 64]; 2] = [
+    leaper_table(&[(1, 1), (1, -1)]),
+    leaper_table(&[(-1, 1), (-1, -1)])
+];
+
+/// Walk each direction from `square` until the board edge or the first blocker
+/// (inclusive). Slow, used to build the lookup tables.
+fn ray_attacks(square: usize, occupancy: u64, directions: &[(i32, i32)]) -> u64 {
+    let (rank, file) = ((square / 8) as i32, (square % 8) as i32);
+    let mut attacks = 0;
+
+    for (rank_step, file_step) in directions {
+        let (mut cur_rank, mut cur_file) = (rank + rank_step, file + file_step);
+
+        while (0..8).contains(&cur_rank) && (0..8).contains(&cur_file) {
+            let bit = 1u64 << (cur_rank * 8 + cur_file);
+            attacks |= bit;
+            if occupancy & bit != 0 {
+                break;
+            }
+
+            cur_rank += rank_step;
+            cur_file += file_step;
+        }
+    }
+
+    attacks
+}
+
+/// Return the squares whose occupancy can change the attacks from `square`. Edge squares
+/// at the end of a ray never block anything behind them, so they are left out.
+fn relevant_mask(square: usize, directions: &[(i32, i32)]) -> u64 {
+    let (rank, file) = ((square / 8) as i32, (square % 8) as i32);
+    let mut mask = 0;
+
+    for (rank_step, file_step) in directions {
+        let (mut cur_rank, mut cur_file) = (rank + rank_step, file + file_step);
+
+        while (0..8).contains(&(cur_rank + rank_step)) && (0..8).contains(&(cur_file + file_step)) {
+            mask |= 1u64 << (cur_rank * 8 + cur_file);
+
+            cur_rank += rank_step;
+            cur_file += file_step;
+        }
+    }
+
+    mask
+}
+
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize
+}
+
+impl Magic {
+    fn index(&self, occupancy: u64) -> usize {
+        self.offset + (((occupancy & self.mask).wrapping_mul(self.magic)) >> self.shift) as usize
+    }
+}
+
+struct SlidingTable {
+    magics: Vec<Magic>,
+    attacks: Vec<u64>
+}
+
+impl SlidingTable {
+    fn new(directions: &[(i32, i32)], square_magics: &[u64; 64]) -> Self {
+        let mut magics = Vec::with_capacity(64);
+        let mut attacks = Vec::new();
+
+        for (square, magic) in square_magics.iter().enumerate() {
+            let mask = relevant_mask(square, directions);
+            let bits = mask.count_ones();
+            let entry = Magic{mask, magic: *magic, shift: 64 - bits, offset: attacks.len()};
+
+            //  Fill the attack set of every subset of the mask (Carry-Rippler).
+            attacks.resize(attacks.len() + (1 << bits), 0);
+            let mut subset: u64 = 0;
+            loop {
+                attacks[entry.index(subset)] = ray_attacks(square, subset, directions);
+
+                subset = subset.wrapping_sub(mask) & mask;
+                if subset == 0 {
+                    break;
+                }
+            }
+
+            magics.push(entry);
+        }
+
+        Self{magics, attacks}
+    }
+
+    fn attacks(&self, square: usize, occupancy: u64) -> u64 {
+        self.attacks[self.magics[square].index(occupancy)]
+    }
+}
+
+/// Squares strictly between, and the full line through, every aligned pair of squares.
+/// Both are empty for pairs that share no rank, file or diagonal.
+struct LineTables {
+    between: Vec<u64>,
+    line: Vec<u64>
+}
+
+impl LineTables {
+    fn new() -> Self {
+        let mut between = vec![0u64; 64 * 64];
+        let mut line = vec![0u64; 64 * 64];
+
+        for from in 0..64 {
+            for (rank_step, file_step) in KING_STEPS {
+                let full = (1u64 << from) |
+                    ray_attacks(from, 0, &[(rank_step, file_step)]) |
+                    ray_attacks(from, 0, &[(-rank_step, -file_step)]);
+
+                let mut passed: u64 = 0;
+                let (mut rank, mut file) = ((from / 8) as i32 + rank_step, (from % 8) as i32 + file_step);
+                while (0..8).contains(&rank) && (0..8).contains(&file) {
+                    let to = (rank * 8 + file) as usize;
+                    between[from * 64 + to] = passed;
+                    line[from * 64 + to] = full;
+                    passed |= 1 << to;
+
+                    rank += rank_step;
+                    file += file_step;
+                }
+            }
+        }
+
+        Self{between, line}
+    }
+}
+
+lazy_static! {
+    static ref LINE_TABLES: LineTables = LineTables::new();
+    static ref BISHOP_TABLE: SlidingTable = SlidingTable::new(&BISHOP_DIRECTIONS, &BISHOP_MAGICS);
+    static ref ROOK_TABLE: SlidingTable = SlidingTable::new(&ROOK_DIRECTIONS, &ROOK_MAGICS);
+}
+
+pub(super) fn knight_attacks(square: usize) -> Bitboard {
+    Bitboard(KNIGHT_ATTACKS[square])
+}
+
+pub(super) fn king_attacks(square: usize) -> Bitboard {
+    Bitboard(KING_ATTACKS[square])
+}
+
+/// Return the squares a pawn of `color` on `square` attacks.
+pub(super) fn pawn_attacks(color: Color, square: usize) -> Bitboard {
+    let color_idx: usize = color.into();
+
+    Bitboard(PAWN_ATTACKS[color_idx][square])
+}
+
+pub(super) fn bishop_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    Bitboard(BISHOP_TABLE.attacks(square, occupancy.0))
+}
+
+pub(super) fn rook_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    Bitboard(ROOK_TABLE.attacks(square, occupancy.0))
+}
+
+pub(super) fn queen_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupancy) | rook_attacks(square, occupancy)
+}
+
+/// Return the squares strictly between `from` and `to` if they are aligned.
+pub(super) fn between(from: usize, to: usize) -> Bitboard {
+    Bitboard(LINE_TABLES.between[from * 64 + to])
+}
+
+/// Return the full edge-to-edge line through `from` and `to` if they are aligned.
+pub(super) fn line(from: usize, to: usize) -> Bitboard {
+    Bitboard(LINE_TABLES.line[from * 64 + to])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaper_attacks() {
+        //  Knight on b1 attacks a3, c3, d2.
+        assert_eq!(knight_attacks(1).0, (1 << 16) | (1 << 18) | (1 << 11));
+        assert_eq!(king_attacks(0).len(), 3);
+        assert_eq!(pawn_attacks(Color::White, 12).0, (1 << 19) | (1 << 21));
+        assert_eq!(pawn_attacks(Color::Black, 8).0, 1 << 1);
+    }
+
+    #[test]
+    fn test_magics_are_collision_free() {
+        for (directions, magics) in [(&BISHOP_DIRECTIONS, &BISHOP_MAGICS), (&ROOK_DIRECTIONS, &ROOK_MAGICS)] {
+            let table = SlidingTable::new(directions, magics);
+
+            for (square, magic) in table.magics.iter().enumerate() {
+                let mut subset: u64 = 0;
+                loop {
+                    assert_eq!(table.attacks[magic.index(subset)], ray_attacks(square, subset, directions));
+
+                    subset = subset.wrapping_sub(magic.mask) & magic.mask;
+                    if subset == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sliding_attacks_match_rays() {
+        let occupancies = [0u64, 0x0000_0010_2400_8100, 0xffff_0000_0000_ffff, 0x0042_0018_1800_4200];
+
+        for square in 0..64 {
+            for occupancy in occupancies {
+                assert_eq!(
+                    bishop_attacks(square, Bitboard(occupancy)).0,
+                    ray_attacks(square, occupancy, &BISHOP_DIRECTIONS)
+                );
+                assert_eq!(
+                    rook_attacks(square, Bitboard(occupancy)).0,
+                    ray_attacks(square, occupancy, &ROOK_DIRECTIONS)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_lines() {
+        //  a1 to h8 along the long diagonal.
+        assert_eq!(between(0, 63).len(), 6);
+        assert_eq!(line(9, 18).len(), 8);
+        assert_eq!(between(4, 7).0, (1 << 5) | (1 << 6));
+        assert!(between(0, 17).is_empty());
+        assert!(line(0, 17).is_empty());
+    }
+}
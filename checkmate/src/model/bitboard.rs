@@ -0,0 +1,188 @@
+use std::ops;
+
+use super::position::Position;
+
+/// `Bitboard` is a set of board positions packed into a `u64`, one bit per square.
+/// Bit `n` corresponds to [`Position::index`] `n`, so `a1` is the least significant bit
+/// and `h8` the most significant.
+///
+/// Is [`Copy`] and iterates the contained positions from `a1` towards `h8`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const ALL: Bitboard = Bitboard(!0);
+    pub const LIGHT_SQUARES: Bitboard = Bitboard(0x55aa55aa55aa55aa);
+    pub const DARK_SQUARES: Bitboard = Bitboard(!0x55aa55aa55aa55aa);
+
+    pub fn from_position(position: &Position) -> Self {
+        Self::from_square(position.index())
+    }
+
+    pub(super) const fn from_square(square: usize) -> Self {
+        Self(1 << square)
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        position.is_valid() && self.has_square(position.index())
+    }
+
+    pub(super) fn has_square(&self, square: usize) -> bool {
+        self.0 & (1 << square) != 0
+    }
+
+    /// Return the number of positions in the set.
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Return the lowest position in the set, if there is one.
+    pub fn first(&self) -> Option<Position> {
+        self.first_square().map(Position::from_index)
+    }
+
+    pub(super) fn first_square(&self) -> Option<usize> {
+        match self.0 {
+            0 => None,
+            bits => Some(bits.trailing_zeros() as usize)
+        }
+    }
+
+    /// Iterate the square indices of the set rather than full [`Position`]s.
+    pub(super) fn squares(self) -> Squares {
+        Squares(self.0)
+    }
+}
+
+pub(super) struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+
+        Some(square)
+    }
+}
+
+pub struct BitboardIter(Squares);
+
+impl Iterator for BitboardIter {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Position::from_index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.0.0.count_ones() as usize;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for BitboardIter {}
+
+impl IntoIterator for Bitboard {
+    type Item = Position;
+    type IntoIter = BitboardIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BitboardIter(self.squares())
+    }
+}
+
+impl ops::BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self::Output {
+        Self(self.0 & other.0)
+    }
+}
+
+impl ops::BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self::Output {
+        Self(self.0 | other.0)
+    }
+}
+
+impl ops::BitXor for Bitboard {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self::Output {
+        Self(self.0 ^ other.0)
+    }
+}
+
+impl ops::Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
+
+impl ops::BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, other: Self) {
+        self.0 &= other.0;
+    }
+}
+
+impl ops::BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl ops::BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, other: Self) {
+        self.0 ^= other.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iteration() {
+        let board = Bitboard::from_position(&Position::new(1, 4)) | Bitboard::from_position(&Position::new(0, 1));
+
+        assert_eq!(board.len(), 2);
+        assert_eq!(board.first(), Some(Position::new(0, 1)));
+        assert_eq!(
+            board.into_iter().collect::<Vec<Position>>(),
+            vec![Position::new(0, 1), Position::new(1, 4)]
+        );
+    }
+
+    #[test]
+    fn test_contains() {
+        let board = Bitboard::from_position(&Position::new(7, 7));
+
+        assert!(board.contains(&Position::new(7, 7)));
+        assert!(!board.contains(&Position::new(7, 6)));
+        assert!(!board.contains(&Position::new(0, 0).left()));
+    }
+
+    #[test]
+    fn test_square_colors() {
+        assert!(Bitboard::DARK_SQUARES.contains(&Position::new(0, 0)));
+        assert!(Bitboard::LIGHT_SQUARES.contains(&Position::new(0, 7)));
+        assert!(Bitboard::LIGHT_SQUARES.contains(&Position::new(7, 0)));
+        assert_eq!(Bitboard::LIGHT_SQUARES.len(), 32);
+    }
+}
@@ -4,14 +4,22 @@ use super::color::Color;
 use super::position::Position;
 use super::piece_type::PieceType;
 use super::piece::Piece;
+use super::bitboard::Bitboard;
+use super::attacks;
 
 /// `Board` encapsulates a single board state and provides methods for its comprehension.
 ///
+/// Pieces are stored as one [`Bitboard`] per color and piece type, alongside a per-square
+/// lookup that backs indexing.
+///
 /// Usually used as a member of [`State`].
+///
+/// [`State`]: super::State
 #[derive(Clone)]
 pub struct Board {
-    positions: [[Option<Piece>; 8]; 8],
-    color_positions: [Vec<Position>; 2]
+    squares: [Option<Piece>; 64],
+    pieces: [[Bitboard; 6]; 2],
+    colors: [Bitboard; 2]
 }
 
 impl ops::Index<&Position> for Board {
@@ -31,7 +39,7 @@ impl ops::Index<&Position> for Board {
             return &None;
         }
 
-        &self.positions[position.rank][position.file]
+        &self.squares[position.index()]
     }
 }
 
@@ -47,67 +55,146 @@ impl ops::Index<Position> for Board {
 }
 
 impl Board {
-    pub(super) fn new(positions: [[Option<Piece>; 8]; 8], color_positions: [Vec<Position>; 2]) -> Self {
-        Self{positions, color_positions}
+    pub(super) fn empty() -> Self {
+        Self{
+            squares: [const { None }; 64],
+            pieces: [[Bitboard::EMPTY; 6]; 2],
+            colors: [Bitboard::EMPTY; 2]
+        }
     }
 
     /// Return the positions occupied by pieces of the given [`Color`].
-    pub fn positions_for(&self, color: Color) -> &Vec<Position> {
+    pub fn positions_for(&self, color: Color) -> Vec<Position> {
+        self.occupied_by(color).into_iter().collect()
+    }
+
+    /// Return the positions occupied by pieces of the given [`Color`] as a [`Bitboard`].
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
         let index: usize = color.into();
-        &self.color_positions[index]
+        self.colors[index]
+    }
+
+    /// Return the positions occupied by pieces of the given [`Color`] and [`PieceType`].
+    pub fn positions_of(&self, color: Color, piece_type: PieceType) -> Bitboard {
+        let (color_idx, type_idx): (usize, usize) = (color.into(), piece_type.into());
+        self.pieces[color_idx][type_idx]
+    }
+
+    /// Return the positions occupied by any piece.
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    pub fn king_position(&self, color: Color) -> Option<Position> {
+        self.positions_of(color, PieceType::King).first()
+    }
+
+    /// Return the squares the piece on `position` attacks, whether or not they hold a
+    /// piece of its own color. Empty if `position` is empty.
+    pub fn attacks_from(&self, position: &Position) -> Bitboard {
+        let piece = match &self[position] {
+            Some(piece) => piece,
+            None => return Bitboard::EMPTY
+        };
+
+        let square = position.index();
+        match piece.piece_type {
+            PieceType::Pawn => attacks::pawn_attacks(piece.color, square),
+            PieceType::Knight => attacks::knight_attacks(square),
+            PieceType::Bishop => attacks::bishop_attacks(square, self.occupied()),
+            PieceType::Rook => attacks::rook_attacks(square, self.occupied()),
+            PieceType::Queen => attacks::queen_attacks(square, self.occupied()),
+            PieceType::King => attacks::king_attacks(square)
+        }
     }
 
-    fn apply_move(
+    fn positions_of_type(&self, piece_type: PieceType) -> Bitboard {
+        self.positions_of(Color::White, piece_type) | self.positions_of(Color::Black, piece_type)
+    }
+
+    /// Return the pieces of either color attacking `square` when the board is occupied
+    /// as given. Passing an occupancy other than [`Board::occupied`] allows x-ray queries.
+    pub(super) fn attackers_to(&self, square: usize, occupancy: Bitboard) -> Bitboard {
+        let diagonal = self.positions_of_type(PieceType::Bishop) | self.positions_of_type(PieceType::Queen);
+        let orthogonal = self.positions_of_type(PieceType::Rook) | self.positions_of_type(PieceType::Queen);
+
+        (attacks::pawn_attacks(Color::White, square) & self.positions_of(Color::Black, PieceType::Pawn)) |
+            (attacks::pawn_attacks(Color::Black, square) & self.positions_of(Color::White, PieceType::Pawn)) |
+            (attacks::knight_attacks(square) & self.positions_of_type(PieceType::Knight)) |
+            (attacks::king_attacks(square) & self.positions_of_type(PieceType::King)) |
+            (attacks::bishop_attacks(square, occupancy) & diagonal) |
+            (attacks::rook_attacks(square, occupancy) & orthogonal)
+    }
+
+    pub(super) fn put(&mut self, piece: Piece, position: &Position) {
+        let square = position.index();
+        let (color_idx, type_idx): (usize, usize) = (piece.color.into(), piece.piece_type.into());
+        let bit = Bitboard::from_square(square);
+
+        self.take(position);
+        self.pieces[color_idx][type_idx] |= bit;
+        self.colors[color_idx] |= bit;
+        self.squares[square] = Some(piece);
+    }
+
+    pub(super) fn take(&mut self, position: &Position) -> Option<Piece> {
+        let square = position.index();
+        let piece = self.squares[square].take()?;
+
+        let (color_idx, type_idx): (usize, usize) = (piece.color.into(), piece.piece_type.into());
+        let bit = !Bitboard::from_square(square);
+        self.pieces[color_idx][type_idx] &= bit;
+        self.colors[color_idx] &= bit;
+
+        Some(piece)
+    }
+
+    pub(super) fn apply_move(
         &mut self, from: &Position, to: &Position,
         promo: &Option<PieceType>, castle: &Option<(Position, Position)>
     ) {
+        let mut moved_piece = self.take(from).unwrap();
+
+        //  Apply castle, otherwise opponent piece take. The rook is lifted before the
+        //  king lands since they may share a square.
+        match castle {
+            Some((castle_from, castle_to)) => {
+                let rook = self.take(castle_from).unwrap();
+                self.put(rook, castle_to);
+            },
+            None => {
+                self.take(to);
+            }
+        }
+
         //  Apply promotion.
-        let mut moved_piece = self[from].as_ref().unwrap().clone();
         if let Some(new_type) = promo {
-            moved_piece = Piece::new(moved_piece.color.clone(), new_type.clone());
+            moved_piece = Piece::new(moved_piece.color, *new_type);
         }
 
-        //  Apply opponent piece take.
-        if let Some(taken_piece) = self[to].as_ref() {
-            let taken_color_idx: usize = taken_piece.color.into();
-
-            self.color_positions[taken_color_idx] = self.color_positions[taken_color_idx]
-                .drain(..).filter(|p| *p != *to).collect();
-        }
+        self.put(moved_piece, to);
+    }
 
-        let color_index: usize = moved_piece.color.into();
-        let mut position_set = self.color_positions[color_index].clone();
+    /// Reverse [`Board::apply_move`], returning `piece` to `from` and any `taken` piece to
+    /// the position it was taken from.
+    pub(super) fn unapply_move(
+        &mut self, from: &Position, to: &Position, piece: &Piece,
+        taken: Option<(&Piece, &Position)>, castle: &Option<(Position, Position)>
+    ) {
+        self.take(to);
 
-        //  Apply castle.
         if let Some((castle_from, castle_to)) = castle {
-            let rook = self.positions[castle_from.rank][castle_from.file]
-                .as_ref().unwrap().clone();
-
-            self.positions[castle_from.rank][castle_from.file] = None;
-            self.positions[castle_to.rank][castle_to.file] = Some(rook.clone());
-            
-            position_set = position_set.drain(..).filter(|p| *p != *castle_from).collect();
-            position_set.push(castle_to.clone());
+            let rook = self.take(castle_to).unwrap();
+            self.put(rook, castle_from);
         }
 
-        //  Apply move.
-        self.positions[from.rank][from.file] = None;
-        self.positions[to.rank][to.file] = Some(moved_piece.clone());
+        self.put(piece.clone(), from);
 
-        position_set = position_set.drain(..).filter(|p| *p != *from).collect();
-        position_set.push(to.clone());
-        self.color_positions[color_index] = position_set;
+        if let Some((taken_piece, taken_position)) = taken {
+            self.put(taken_piece.clone(), taken_position);
+        }
     }
 
-    pub(super) fn next_for_move(
-        &self, from: &Position, to: &Position,
-        promo: &Option<PieceType>, castle: &Option<(Position, Position)>
-    ) -> Board {
-        let mut inst = self.clone();
-        inst.apply_move(from, to, promo, castle);
-
-        inst
-    }
 }
 
 #[cfg(test)]
@@ -140,19 +227,10 @@ mod tests {
     
     #[test]
     fn test_next_for_move() {
-        let board = Board::default().next_for_move(
-            &Position::new(1, 4),
-            &Position::new(3, 4),
-            &None, &None
-        ).next_for_move(
-            &Position::new(6, 5),
-            &Position::new(4, 5),
-            &None, &None
-        ).next_for_move(
-            &Position::new(3, 4),
-            &Position::new(4, 5),
-            &None, &None
-        );
+        let mut board = Board::default();
+        board.apply_move(&Position::new(1, 4), &Position::new(3, 4), &None, &None);
+        board.apply_move(&Position::new(6, 5), &Position::new(4, 5), &None, &None);
+        board.apply_move(&Position::new(3, 4), &Position::new(4, 5), &None, &None);
 
         assert_eq!(board[&Position::new(1, 4)], None);
         assert_eq!(
@@ -161,5 +239,63 @@ mod tests {
         );
         assert_eq!(board.positions_for(Color::White).len(), 16);
         assert_eq!(board.positions_for(Color::Black).len(), 15);
+        assert!(board.positions_for(Color::White).contains(&Position::new(4, 5)));
+        assert!(!board.positions_for(Color::Black).contains(&Position::new(4, 5)));
+        assert_eq!(board.occupied_by(Color::Black).len(), 15);
+        assert_eq!(board.positions_of(Color::White, PieceType::Pawn).len(), 8);
+        assert!(board.positions_of(Color::White, PieceType::Pawn).contains(&Position::new(4, 5)));
+
+        let black_pawn = Piece::new(Color::Black, PieceType::Pawn);
+        board.unapply_move(
+            &Position::new(3, 4), &Position::new(4, 5), &Piece::new(Color::White, PieceType::Pawn),
+            Some((&black_pawn, &Position::new(4, 5))), &None
+        );
+        assert_eq!(board[&Position::new(4, 5)], Some(black_pawn));
+        assert_eq!(board[&Position::new(3, 4)], Some(Piece::new(Color::White, PieceType::Pawn)));
+        assert_eq!(board.occupied().len(), 32);
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let board = Board::default();
+
+        //  f3 is covered by the e2 and g2 pawns and the g1 knight.
+        assert_eq!(board.attackers_to(Position::new(2, 5).index(), board.occupied()).len(), 3);
+        assert!(board.attackers_to(Position::new(3, 4).index(), board.occupied()).is_empty());
+    }
+
+    #[test]
+    fn test_attacks_from() {
+        let board = Board::default();
+
+        assert_eq!(board.attacks_from(&Position::new(0, 6)).len(), 3);
+        assert_eq!(board.attacks_from(&Position::new(1, 0)).len(), 1);
+        //  The a1 rook only sees its own neighbours.
+        assert_eq!(board.attacks_from(&Position::new(0, 0)).len(), 2);
+        assert!(board.attacks_from(&Position::new(4, 4)).is_empty());
+    }
+
+    #[test]
+    fn test_next_for_castle() {
+        let mut board = Board::default();
+        board.apply_move(&Position::new(0, 5), &Position::new(2, 5), &None, &None);
+        board.apply_move(&Position::new(0, 6), &Position::new(2, 7), &None, &None);
+        board.apply_move(
+            &Position::new(0, 4), &Position::new(0, 6),
+            &None, &Some((Position::new(0, 7), Position::new(0, 5)))
+        );
+
+        assert_eq!(board.king_position(Color::White), Some(Position::new(0, 6)));
+        assert_eq!(board[&Position::new(0, 5)], Some(Piece::new(Color::White, PieceType::Rook)));
+        assert_eq!(board[&Position::new(0, 7)], None);
+        assert_eq!(board.occupied().len(), 32);
+
+        board.unapply_move(
+            &Position::new(0, 4), &Position::new(0, 6), &Piece::new(Color::White, PieceType::King),
+            None, &Some((Position::new(0, 7), Position::new(0, 5)))
+        );
+        assert_eq!(board.king_position(Color::White), Some(Position::new(0, 4)));
+        assert_eq!(board[&Position::new(0, 7)], Some(Piece::new(Color::White, PieceType::Rook)));
+        assert_eq!(board[&Position::new(0, 5)], None);
     }
 }
\ No newline at end of file
@@ -3,6 +3,7 @@ use super::position::Position;
 use super::piece_type::PieceType;
 use super::piece::Piece;
 use super::board::Board;
+use crate::errors::ValidationError;
 
 struct BoardPlacement {
     piece: Piece,
@@ -16,6 +17,12 @@ pub struct BoardBuilder {
     placements: Vec<BoardPlacement>
 }
 
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BoardBuilder {
     pub fn new() -> Self {
         Self{
@@ -27,19 +34,33 @@ impl BoardBuilder {
         self.placements.push(BoardPlacement{piece, position});
     }
 
-    //  TODO: Remove clones.
     pub fn build(self) -> Board {
-        let mut positions: [[Option<Piece>; 8]; 8] = Default::default();
-        let mut piece_positions: [Vec<Position>; 2] = Default::default();
-
-        for placement in &self.placements {
-            positions[placement.position.rank][placement.position.file] = Some(placement.piece.clone());
-            
-            let color_idx: usize = placement.piece.color.into();
-            piece_positions[color_idx].push(placement.position.clone());
+        let mut board = Board::empty();
+
+        for placement in self.placements {
+            board.put(placement.piece, &placement.position);
+        }
+
+        board
+    }
+
+    /// Build the board, failing on placements off the board or on an occupied square
+    /// rather than letting the later placement win.
+    pub fn try_build(self) -> Result<Board, ValidationError> {
+        let mut board = Board::empty();
+
+        for placement in self.placements {
+            if !placement.position.is_valid() {
+                return Err(ValidationError::OffBoard{position: placement.position});
+            }
+            if board[&placement.position].is_some() {
+                return Err(ValidationError::OccupiedSquare{position: placement.position});
+            }
+
+            board.put(placement.piece, &placement.position);
         }
 
-        Board::new(positions, piece_positions)
+        Ok(board)
     }
 }
 
@@ -3,15 +3,26 @@ use readonly;
 pub use super::color::Color;
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum EndCondition {
     Checkmate,
     Stalemate,
     InsufficientMateriel,
-    Surrender
+    /// Claimable once fifty moves by each side pass without a capture or pawn move.
+    FiftyMoveRule,
+    /// Automatic once seventy-five moves by each side pass without a capture or pawn move.
+    SeventyFiveMoveRule,
+    /// Claimable once the same position occurs for the third time.
+    ThreefoldRepetition,
+    /// Automatic once the same position occurs for the fifth time.
+    FivefoldRepetition,
+    Surrender,
+    Timeout
 }
 
 #[readonly::make]
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize, serde::Deserialize))]
 pub struct EndResult {
     pub condition: EndCondition,
     pub winner: Option<Color>
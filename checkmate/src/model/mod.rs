@@ -1,23 +1,28 @@
 mod color;
 mod position;
+mod bitboard;
+mod attacks;
 mod piece_type;
 mod piece;
 mod move_repr;
 mod board;
 mod state;
 mod end;
+mod zobrist;
 mod move_rules;
-mod moves_builder;
 mod board_builder;
 mod state_builder;
+#[cfg(feature = "serialization")]
+mod serialization;
 
 pub use color::Color;
 pub use position::{RANKS, FILES, Position};
+pub use bitboard::{Bitboard, BitboardIter};
 pub use piece_type::PieceType;
 pub use piece::Piece;
 pub use move_repr::{Move, CastleMoves};
 pub use board::Board;
-pub use state::State;
+pub use state::{State, Undo};
 pub use end::{EndCondition, EndResult};
 pub use board_builder::BoardBuilder;
 pub use state_builder::StateBuilder;
@@ -1,6 +1,7 @@
 use readonly;
 use bitmask_enum::bitmask;
 
+use super::color::Color;
 use super::position::Position;
 use super::piece_type::PieceType;
 use super::piece::Piece;
@@ -12,6 +13,34 @@ pub enum CastleMoves {
     QueenSide
 }
 
+/// Files of the kingside and queenside castling rooks in standard chess.
+pub(super) const STANDARD_ROOK_FILES: [usize; 2] = [7, 0];
+
+impl CastleMoves {
+    /// Return the castle whose rook starts on `position` for `color`, given the files of
+    /// its kingside and queenside castling rooks.
+    pub fn for_rook_origin(position: &Position, color: Color, rook_files: &[usize; 2]) -> CastleMoves {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        if position.rank != back_rank {
+            return CastleMoves::none();
+        }
+
+        match position.file {
+            file if file == rook_files[0] => CastleMoves::KingSide,
+            file if file == rook_files[1] => CastleMoves::QueenSide,
+            _ => CastleMoves::none()
+        }
+    }
+
+    /// Return the index of a single side, kingside first.
+    pub(super) fn side_index(&self) -> usize {
+        match *self == CastleMoves::KingSide {
+            true => 0,
+            false => 1
+        }
+    }
+}
+
 #[readonly::make]
 #[derive(Clone, Debug)]
 pub struct Move {
@@ -39,14 +68,44 @@ impl Move {
         variant
     }
 
-    pub fn disallowed_castle(&self) -> Option<CastleMoves> {
-        if let Some(castle) = &self.castle {
-            return Some(match castle.0.file > castle.1.file {
-                true => CastleMoves::KingSide,
-                false => CastleMoves::QueenSide
-            });
+    /// Return the castle this move is, if it is one.
+    pub fn castle_side(&self) -> Option<CastleMoves> {
+        let (rook_from, _) = self.castle.as_ref()?;
+
+        Some(match rook_from.file > self.from.file {
+            true => CastleMoves::KingSide,
+            false => CastleMoves::QueenSide
+        })
+    }
+
+    /// Return the castles the moving color gives up by playing this move: both once the
+    /// king moves, or one side once its rook leaves the starting square. `rook_files` are
+    /// the mover's castling rook files.
+    pub fn disallowed_castle(&self, rook_files: &[usize; 2]) -> Option<CastleMoves> {
+        let disallowed = match self.piece.piece_type {
+            PieceType::King => CastleMoves::all(),
+            PieceType::Rook => CastleMoves::for_rook_origin(&self.from, self.piece.color, rook_files),
+            _ => CastleMoves::none()
+        };
+
+        match disallowed.is_none() {
+            true => None,
+            false => Some(disallowed)
+        }
+    }
+
+    /// Return the castles the opponent loses because this move takes a rook on its
+    /// starting square. `rook_files` are the opponent's castling rook files.
+    pub fn disallowed_opponent_castle(&self, rook_files: &[usize; 2]) -> Option<CastleMoves> {
+        let taken = self.taken.as_ref()?;
+        if taken.piece_type != PieceType::Rook {
+            return None;
         }
 
-        None
+        let disallowed = CastleMoves::for_rook_origin(&self.to, taken.color, rook_files);
+        match disallowed.is_none() {
+            true => None,
+            false => Some(disallowed)
+        }
     }
 }
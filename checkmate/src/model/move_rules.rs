@@ -4,165 +4,287 @@ use super::piece::Piece;
 use super::piece_type::PieceType;
 use super::move_repr::{Move, CastleMoves};
 use super::state::State;
-use super::moves_builder::MovesBuilder;
+use super::bitboard::Bitboard;
+use super::attacks;
 
-type MoveComputeFn = fn (builder: &mut MovesBuilder, state: &State, position: &Position, piece: &Piece);
+type MoveComputeFn = fn (gen: &mut MoveGen, square: usize, piece: &Piece);
 
-fn pawn_moves(builder: &mut MovesBuilder, state: &State, position: &Position, piece: &Piece) {
-    let end_rank = if piece.color == Color::White { 7 } else { 0 };
-    let promotions = &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+/// `MoveGen` emits the legal moves of one color in one position. The constraints shared
+/// by every piece are computed once up front: the pieces checking the king, the squares
+/// that resolve a single check, and the pieces pinned against the king. Moves that
+/// violate them are never produced, so no move has to be played out to be validated.
+struct MoveGen<'t> {
+    state: &'t State,
+    color: Color,
+    king: Option<usize>,
+    occupied: Bitboard,
+    allies: Bitboard,
+    enemies: Bitboard,
+    checkers: Bitboard,
+    check_mask: Bitboard,
+    pinned: Bitboard,
+    moves: Vec<Move>
+}
+
+impl<'t> MoveGen<'t> {
+    fn new(state: &'t State, color: Color) -> Self {
+        let board = &state.board;
+        let occupied = board.occupied();
+        let allies = board.occupied_by(color);
+        let enemies = board.occupied_by(!color);
+        let king = board.positions_of(color, PieceType::King).first_square();
+
+        let mut checkers = Bitboard::EMPTY;
+        let mut check_mask = Bitboard::ALL;
+        let mut pinned = Bitboard::EMPTY;
+        if let Some(king) = king {
+            checkers = board.attackers_to(king, occupied) & enemies;
+            check_mask = match checkers.first_square() {
+                None => Bitboard::ALL,
+                Some(checker) if checkers.len() == 1 => checkers | attacks::between(king, checker),
+                Some(_) => Bitboard::EMPTY
+            };
+
+            //  Enemy sliders that would attack the king if only enemy pieces stood on the
+            //  board pin the single allied piece between them, if there is one.
+            let orthogonal = board.positions_of(!color, PieceType::Rook) | board.positions_of(!color, PieceType::Queen);
+            let diagonal = board.positions_of(!color, PieceType::Bishop) | board.positions_of(!color, PieceType::Queen);
+            let snipers = (attacks::rook_attacks(king, enemies) & orthogonal) |
+                (attacks::bishop_attacks(king, enemies) & diagonal);
+
+            for sniper in snipers.squares() {
+                let blockers = attacks::between(king, sniper) & occupied;
+                if blockers.len() == 1 && !(blockers & allies).is_empty() {
+                    pinned |= blockers;
+                }
+            }
+        }
+
+        Self{
+            state, color, king, occupied, allies, enemies,
+            checkers, check_mask, pinned,
+            moves: Vec::with_capacity(48)
+        }
+    }
+
+    /// Return the destinations a non-king piece on `square` may legally reach, given
+    /// checks and pins.
+    fn legal_mask(&self, square: usize) -> Bitboard {
+        let mut mask = self.check_mask & !self.allies;
 
-    let mut push_move = |to: &Position, iff_take: bool| -> bool {
-        let taken = &state.board[to];
-        if iff_take != taken.is_some() {
-            return false;
+        if let Some(king) = self.king {
+            if self.pinned.has_square(square) {
+                mask &= attacks::line(king, square);
+            }
         }
 
-        //  TODO: Yikes!
-        let new_move = Move::new(
-            position.clone(), to.clone(), piece.clone(),
-            taken.clone(), None, None
-        );
-        if to.rank == end_rank {
+        mask
+    }
+
+    fn is_attacked(&self, square: usize, occupied: Bitboard) -> bool {
+        !(self.state.board.attackers_to(square, occupied) & self.enemies).is_empty()
+    }
+
+    fn push(&mut self, from: usize, to: usize, piece: &Piece, promotion: Option<PieceType>) {
+        let to_position = Position::from_index(to);
+        let taken = self.state.board[&to_position].clone();
+
+        self.moves.push(Move::new(
+            Position::from_index(from), to_position, piece.clone(),
+            taken, promotion, None
+        ));
+    }
+
+    fn push_targets(&mut self, from: usize, targets: Bitboard, piece: &Piece) {
+        for to in targets.squares() {
+            self.push(from, to, piece, None);
+        }
+    }
+
+    fn compute_for(&mut self, square: usize, piece: &Piece) {
+        let compute: MoveComputeFn = match piece.piece_type {
+            PieceType::Pawn => pawn_moves,
+            PieceType::Bishop => bishop_moves,
+            PieceType::Rook => rook_moves,
+            PieceType::Knight => knight_moves,
+            PieceType::Queen => queen_moves,
+            PieceType::King => king_moves
+        };
+
+        compute(self, square, piece);
+    }
+}
+
+fn pawn_moves(gen: &mut MoveGen, square: usize, piece: &Piece) {
+    let end_rank = if piece.color == Color::White { 7 } else { 0 };
+    let promotions = &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+    let mask = gen.legal_mask(square);
+
+    let push_move = |gen: &mut MoveGen, to: usize| {
+        if to / 8 == end_rank {
             for promo in promotions {
-                builder.push(new_move.with_promotion(*promo));
+                gen.push(square, to, piece, Some(*promo));
             }
         }
         else {
-            builder.push(new_move);
+            gen.push(square, to, piece, None);
         }
-
-        true
     };
 
     //  Walk forward.
-    let forward_one = &position.forward(piece.color);
-    if push_move(forward_one, false) { 
-        let initial_rank = if piece.color == Color::White { 1 } else { 6 };
+    let position = Position::from_index(square);
+    let forward_one = position.forward(piece.color);
+    if forward_one.is_valid() && !gen.occupied.has_square(forward_one.index()) {
+        if mask.has_square(forward_one.index()) {
+            push_move(gen, forward_one.index());
+        }
 
         //  Push from initial rank.
-        if position.rank == initial_rank {
-            push_move(&forward_one.forward(piece.color), false);
+        let initial_rank = if piece.color == Color::White { 1 } else { 6 };
+        let forward_two = forward_one.forward(piece.color);
+        if position.rank == initial_rank && !gen.occupied.has_square(forward_two.index()) && mask.has_square(forward_two.index()) {
+            push_move(gen, forward_two.index());
         }
     }
 
     //  Normal attacks.
-    let attack_right = &forward_one.right();
-    let attack_left = &forward_one.left();
-    push_move(attack_right, true);
-    push_move(attack_left, true);
-
-    //  En-passant.
-    if let Some(target) = state.get_en_passant_position() {
-        if attack_right == target {
-            let taken = &state.board[&position.right()];
-            builder.push(Move::new(
-                position.clone(), attack_right.clone(), piece.clone(),
-                taken.clone(), None, None
-            ));
+    let attacks = attacks::pawn_attacks(piece.color, square);
+    for to in (attacks & gen.enemies & mask).squares() {
+        push_move(gen, to);
+    }
+
+    //  En-passant. Taking removes two pieces from one rank, which can expose the king in
+    //  ways a pin test misses, so the result is checked by replaying the occupancy.
+    if gen.color != gen.state.active_color {
+        return;
+    }
+    if let Some(target) = gen.state.get_en_passant_position() {
+        let to = target.index();
+        if !attacks.has_square(to) {
+            return;
         }
-        if attack_left == target {
-            let taken = &state.board[&position.left()];
-            builder.push(Move::new(
-                position.clone(), attack_left.clone(), piece.clone(),
-                taken.clone(), None, None
+
+        let taken_position = Position::new(position.rank, target.file);
+        let taken_bit = Bitboard::from_position(&taken_position);
+        let after = (gen.occupied ^ Bitboard::from_square(square) ^ taken_bit) | Bitboard::from_square(to);
+
+        let exposed = match gen.king {
+            Some(king) => !(gen.state.board.attackers_to(king, after) & gen.enemies & !taken_bit).is_empty(),
+            None => false
+        };
+        if !exposed {
+            gen.moves.push(Move::new(
+                position, target.clone(), piece.clone(),
+                gen.state.board[&taken_position].clone(), None, None
             ));
         }
     }
 }
 
-fn bishop_moves(builder: &mut MovesBuilder, _state: &State, _position: &Position, _piece: &Piece) {
-    builder.push_while_valid(|pos| pos.up().left());
-    builder.push_while_valid(|pos| pos.up().right());
-    builder.push_while_valid(|pos| pos.down().left());
-    builder.push_while_valid(|pos| pos.down().right());
+fn bishop_moves(gen: &mut MoveGen, square: usize, piece: &Piece) {
+    let targets = attacks::bishop_attacks(square, gen.occupied) & gen.legal_mask(square);
+
+    gen.push_targets(square, targets, piece);
 }
 
-fn rook_moves(builder: &mut MovesBuilder, _state: &State, _position: &Position, _piece: &Piece) {
-    builder.push_while_valid(|pos| pos.up());
-    builder.push_while_valid(|pos| pos.down());
-    builder.push_while_valid(|pos| pos.left());
-    builder.push_while_valid(|pos| pos.right());
+fn rook_moves(gen: &mut MoveGen, square: usize, piece: &Piece) {
+    let targets = attacks::rook_attacks(square, gen.occupied) & gen.legal_mask(square);
+
+    gen.push_targets(square, targets, piece);
 }
 
-fn knight_moves(builder: &mut MovesBuilder, _state: &State, position: &Position, _piece: &Piece) {
-    builder.push_if_valid(&position.up().up().left());
-    builder.push_if_valid(&position.up().up().right());
-    builder.push_if_valid(&position.down().down().left());
-    builder.push_if_valid(&position.down().down().right());
-    builder.push_if_valid(&position.left().left().up());
-    builder.push_if_valid(&position.right().right().up());
-    builder.push_if_valid(&position.left().left().down());
-    builder.push_if_valid(&position.right().right().down());
+fn knight_moves(gen: &mut MoveGen, square: usize, piece: &Piece) {
+    let targets = attacks::knight_attacks(square) & gen.legal_mask(square);
+
+    gen.push_targets(square, targets, piece);
 }
 
-fn queen_moves(builder: &mut MovesBuilder, _state: &State, _position: &Position, _piece: &Piece) {
-    builder.push_while_valid(|pos| pos.up().left());
-    builder.push_while_valid(|pos| pos.up().right());
-    builder.push_while_valid(|pos| pos.down().left());
-    builder.push_while_valid(|pos| pos.down().right());
-    builder.push_while_valid(|pos| pos.up());
-    builder.push_while_valid(|pos| pos.down());
-    builder.push_while_valid(|pos| pos.left());
-    builder.push_while_valid(|pos| pos.right());
+fn queen_moves(gen: &mut MoveGen, square: usize, piece: &Piece) {
+    let targets = attacks::queen_attacks(square, gen.occupied) & gen.legal_mask(square);
+
+    gen.push_targets(square, targets, piece);
 }
 
-fn king_moves(builder: &mut MovesBuilder, state: &State, position: &Position, piece: &Piece) {
-    builder.push_if_valid(&position.up().left());
-    builder.push_if_valid(&position.up().right());
-    builder.push_if_valid(&position.down().left());
-    builder.push_if_valid(&position.down().right());
-    builder.push_if_valid(&position.up());
-    builder.push_if_valid(&position.down());
-    builder.push_if_valid(&position.left());
-    builder.push_if_valid(&position.right());
-
-    let mut push_castle = |direction_fn: fn (position: Position) -> Position, castle_check: CastleMoves| {
-        let mut cur_position = direction_fn(position.clone());
-
-        while cur_position.is_valid() {
-            if let Some(piece_here) = &state.board[&cur_position] {
-                let valid = piece_here.color == piece.color &&
-                    piece_here.piece_type == PieceType::Rook &&
-                    state.get_allowed_castles(piece_here.color).contains(castle_check);
-                if !valid {
-                    return;
-                }
-                
-                builder.push(Move::new(
-                    position.clone(), direction_fn(direction_fn(position.clone())), piece.clone(), None,
-                    None, Some((cur_position.clone(), direction_fn(position.clone())))
-                ));
-            }
+fn king_moves(gen: &mut MoveGen, square: usize, piece: &Piece) {
+    //  The king is lifted off the board so it can't shelter behind itself along the
+    //  line of a checking slider.
+    let without_king = gen.occupied ^ Bitboard::from_square(square);
+    for to in (attacks::king_attacks(square) & !gen.allies).squares() {
+        if !gen.is_attacked(to, without_king) {
+            gen.push(square, to, piece, None);
+        }
+    }
+
+    if !gen.checkers.is_empty() {
+        return;
+    }
+
+    //  Castling needs the king and rook on their starting squares with the right still
+    //  held, every square either of them crosses empty, and no square the king crosses
+    //  attacked. The king is already known not to be in check.
+    let back_rank = if piece.color == Color::White { 0 } else { 7 };
+    let allowed = gen.state.get_allowed_castles(piece.color);
+    if square / 8 != back_rank {
+        return;
+    }
 
-            cur_position = direction_fn(cur_position);
+    //  The king and rook end on the same squares wherever they start, as in Chess960.
+    for (side, king_dest_file, rook_dest_file) in [(CastleMoves::KingSide, 6, 5), (CastleMoves::QueenSide, 2, 3)] {
+        if !allowed.contains(side) {
+            continue;
         }
-    };
-    push_castle(|pos| pos.right(), CastleMoves::KingSide);
-    push_castle(|pos| pos.left(), CastleMoves::QueenSide);
+
+        let rook_file = gen.state.get_castle_rook_file(piece.color, side);
+        let rook_square = back_rank * 8 + rook_file;
+        if !gen.state.board.positions_of(piece.color, PieceType::Rook).has_square(rook_square) {
+            continue;
+        }
+
+        let (king_dest, rook_dest) = (back_rank * 8 + king_dest_file, back_rank * 8 + rook_dest_file);
+        let king_path = attacks::between(square, king_dest) | Bitboard::from_square(king_dest);
+        let rook_path = attacks::between(rook_square, rook_dest) | Bitboard::from_square(rook_dest);
+        let castling_pieces = Bitboard::from_square(square) | Bitboard::from_square(rook_square);
+        if !((king_path | rook_path) & gen.occupied & !castling_pieces).is_empty() {
+            continue;
+        }
+        //  The castling rook may have been shielding the king's path along the back rank.
+        if king_path.squares().any(|path_square| gen.is_attacked(path_square, gen.occupied & !castling_pieces)) {
+            continue;
+        }
+
+        gen.moves.push(Move::new(
+            Position::from_index(square), Position::from_index(king_dest), piece.clone(), None,
+            None, Some((Position::from_index(rook_square), Position::from_index(rook_dest)))
+        ));
+    }
+}
+
+/// Return every legal move for pieces of `color`.
+pub fn compute_legal_moves(state: &State, color: Color) -> Vec<Move> {
+    let mut gen = MoveGen::new(state, color);
+
+    for square in state.board.occupied_by(color).squares() {
+        let piece = state.board[&Position::from_index(square)].as_ref().unwrap();
+        gen.compute_for(square, piece);
+    }
+
+    gen.moves
 }
 
+/// Return the legal moves of the piece at `position`.
 pub fn compute_moves_for(state: &State, position: &Position) -> Vec<Move> {
     let piece = state.board[position].as_ref().unwrap();
 
-    let compute: MoveComputeFn = match piece.piece_type {
-        PieceType::Pawn => pawn_moves,
-        PieceType::Bishop => bishop_moves,
-        PieceType::Rook => rook_moves,
-        PieceType::Knight => knight_moves,
-        PieceType::Queen => queen_moves,
-        PieceType::King => king_moves
-    };
-
-    let mut builder = MovesBuilder::new(state, position);
-    compute(&mut builder, state, position, &piece);
+    let mut gen = MoveGen::new(state, piece.color);
+    gen.compute_for(position.index(), piece);
 
-    builder.build()
+    gen.moves
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::formats::{ToFEN, ToState};
 
     #[test]
     fn test_pawn_moves() {
@@ -185,4 +307,67 @@ mod tests {
         assert_eq!(moves[0].to, Position::new(2, 0));
         assert_eq!(moves[1].to, Position::new(2, 2));
     }
+
+    #[test]
+    fn test_blocked_by_ally() {
+        let state = State::default();
+
+        assert_eq!(compute_moves_for(&state, &Position::new(0, 0)).len(), 0);
+        assert_eq!(compute_moves_for(&state, &Position::new(0, 4)).len(), 0);
+    }
+
+    #[test]
+    fn test_pinned_piece() {
+        //  The e2 knight is pinned by the e8 rook, the d2 bishop may only walk the pin ray.
+        let state = "4r2k/8/8/b7/8/8/3BN3/4K3 w - - 0 1".to_fen().to_state().unwrap();
+
+        assert_eq!(compute_moves_for(&state, &Position::new(1, 4)).len(), 0);
+        let bishop_moves: Vec<Position> = compute_moves_for(&state, &Position::new(1, 3)).into_iter().map(|m| m.to.clone()).collect();
+        assert_eq!(bishop_moves, vec![Position::new(2, 2), Position::new(3, 1), Position::new(4, 0)]);
+    }
+
+    #[test]
+    fn test_check_evasions() {
+        //  Double check from the rook and knight leaves only king moves.
+        let state = "4r2k/8/8/8/8/3n4/8/R3K3 w - - 0 1".to_fen().to_state().unwrap();
+
+        let moves = state.get_legal_moves();
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|m| m.piece.piece_type == PieceType::King));
+
+        //  A single check may only be blocked or captured.
+        let state = "4r2k/R7/8/8/8/8/8/4K3 w - - 0 1".to_fen().to_state().unwrap();
+        let blocks: Vec<Move> = state.get_legal_moves().into_iter().filter(|m| m.piece.piece_type == PieceType::Rook).collect();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].to, Position::new(6, 4));
+    }
+
+    #[test]
+    fn test_en_passant_discovered_check() {
+        //  Taking en-passant would clear the fifth rank between the king and the rook.
+        let state = "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1".to_fen().to_state().unwrap();
+
+        assert_eq!(compute_moves_for(&state, &Position::new(4, 4)).len(), 1);
+
+        let state = "8/8/8/K2pP3/8/8/8/7k w - d6 0 1".to_fen().to_state().unwrap();
+
+        assert_eq!(compute_moves_for(&state, &Position::new(4, 4)).len(), 2);
+    }
+
+    #[test]
+    fn test_castle_through_check() {
+        let castles = |fen: &str| -> usize {
+            let state = fen.to_fen().to_state().unwrap();
+
+            compute_moves_for(&state, &Position::new(0, 4)).iter().filter(|m| m.castle.is_some()).count()
+        };
+
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"), 2);
+        //  In check.
+        assert_eq!(castles("r3k2r/8/8/8/4r3/8/8/R3K2R w KQkq - 0 1"), 0);
+        //  Passing through f1.
+        assert_eq!(castles("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1"), 1);
+        //  Landing on c1.
+        assert_eq!(castles("r3k2r/8/8/8/8/8/2r5/R3K2R w KQkq - 0 1"), 1);
+    }
 }
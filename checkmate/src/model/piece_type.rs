@@ -21,6 +21,21 @@ impl PieceType {
     }
 }
 
+impl From<PieceType> for usize {
+    /// Return a dense index for the piece type. Useful for indexing per-`PieceType`
+    /// data stored in slices.
+    fn from(piece_type: PieceType) -> Self {
+        match piece_type {
+            PieceType::Pawn => 0,
+            PieceType::Bishop => 1,
+            PieceType::Rook => 2,
+            PieceType::Knight => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -30,4 +45,10 @@ mod tests {
         assert_eq!(PieceType::Pawn.materiel_value(), 1);
         assert_eq!(PieceType::Queen.materiel_value(), 9);
     }
+
+    #[test]
+    fn test_cast_usize() {
+        assert_eq!(Into::<usize>::into(PieceType::Pawn), 0);
+        assert_eq!(Into::<usize>::into(PieceType::King), 5);
+    }
 }
@@ -2,8 +2,8 @@ use readonly;
 
 use super::color::Color;
 
-pub static RANKS: &'static [char] = &['1', '2', '3', '4', '5', '6', '7', '8'];
-pub static FILES: &'static [char] = &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
+pub static RANKS: &[char] = &['1', '2', '3', '4', '5', '6', '7', '8'];
+pub static FILES: &[char] = &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 
 /// `Position` represents a board position without awareness of of underlying board.
 /// Primarily used as an index key of [`Board`].
@@ -19,6 +19,19 @@ impl Position {
         Self{rank, file}
     }
 
+    /// Construct the position for a square index as returned by [`Position::index`].
+    pub fn from_index(index: usize) -> Self {
+        Self{rank: index / 8, file: index % 8}
+    }
+
+    /// Return the square index of this position, counting from `a1` (`0`) to `h8` (`63`)
+    /// in rank-major order. This is the bit index used by [`Bitboard`].
+    ///
+    /// [`Bitboard`]: super::Bitboard
+    pub fn index(&self) -> usize {
+        self.rank * 8 + self.file
+    }
+
     pub fn is_valid(&self) -> bool {
         (self.rank < 8) && (self.file < 8)
     }
@@ -80,7 +93,15 @@ mod tests {
     fn test_validation() {
         let initial = Position::new(1, 1);
 
-        assert_eq!(initial.is_valid(), true);
-        assert_eq!(initial.left().left().is_valid(), false);
+        assert!(initial.is_valid());
+        assert!(!initial.left().left().is_valid());
+    }
+
+    #[test]
+    fn test_index() {
+        assert_eq!(Position::new(0, 0).index(), 0);
+        assert_eq!(Position::new(1, 4).index(), 12);
+        assert_eq!(Position::new(7, 7).index(), 63);
+        assert_eq!(Position::from_index(12), Position::new(1, 4));
     }
 }
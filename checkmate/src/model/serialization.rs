@@ -0,0 +1,351 @@
+use std::fmt;
+
+use regex::Regex;
+use lazy_static::lazy_static;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+
+use crate::errors::ValidationError;
+use crate::formats::{ToAlg, ToFEN, ToUci, ToMove, ToPosition, ToPiece, ToPieceType, ToState};
+use super::color::Color;
+use super::position::Position;
+use super::piece_type::PieceType;
+use super::piece::Piece;
+use super::move_repr::{Move, CastleMoves};
+use super::board::Board;
+use super::state::State;
+
+//  Serde representations of the model, enabled by the `serialization` feature. Each type
+//  is written in the compact text form the formats module already reads, and read back
+//  through the same validating parsers:
+//
+//  - `Color`: "white" or "black".
+//  - `PieceType`: its SAN letter, "P", "N", "B", "R", "Q" or "K".
+//  - `Piece`: its FEN letter, uppercase for white, as in "N" or "n".
+//  - `Position`: algebraic, as in "e4".
+//  - `CastleMoves`: "K", "Q", "KQ" or "-".
+//  - `Board`: the FEN piece placement field.
+//  - `Move`: see `MOVE_RE`.
+//  - `State`: `{"fen": ..., "initial": ..., "moves": [...]}`, where `moves` are UCI from the
+//    `initial` FEN, so repetition history survives. Both may be omitted, and are
+//    replayed and checked against `fen` when present.
+//  - `EndResult`: `{"condition": "checkmate", "winner": "white"}`, with a null winner for
+//    draws.
+
+lazy_static! {
+    //  Piece and origin, any taken piece, destination, any promotion and, for castles, the
+    //  rook's origin and destination. Pieces are FEN letters, as in "Pe7xrd8=Q" or
+    //  "Ke1g1/h1f1".
+    static ref MOVE_RE: Regex = Regex::new(
+        r"^([PNBRQKpnbrqk])([a-h][1-8])(?:x([PNBRQKpnbrqk]))?([a-h][1-8])(?:=([NBRQ]))?(?:/([a-h][1-8])([a-h][1-8]))?$"
+    ).unwrap();
+}
+
+fn serialize_str<S: Serializer>(value: impl fmt::Display, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&value)
+}
+
+/// Deserialize a string and parse it with `parse`, reporting its [`ValidationError`].
+fn deserialize_with<'de, D: Deserializer<'de>, T>(
+    deserializer: D, parse: impl FnOnce(&str) -> Result<T, ValidationError>
+) -> Result<T, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    parse(&value).map_err(D::Error::custom)
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            Color::White => "white",
+            Color::Black => "black"
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, |value| match value {
+            "white" => Ok(Color::White),
+            "black" => Ok(Color::Black),
+            _ => Err(ValidationError::Parse{token: value.to_string()})
+        })
+    }
+}
+
+impl Serialize for PieceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_str(self.to_alg(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PieceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, |value| value.to_alg().to_piece_type())
+    }
+}
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_str(self.to_fen(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, parse_piece)
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_str(self.to_alg(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, parse_position)
+    }
+}
+
+impl Serialize for CastleMoves {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut sides_str = String::new();
+        if self.contains(CastleMoves::KingSide) {
+            sides_str.push('K');
+        }
+        if self.contains(CastleMoves::QueenSide) {
+            sides_str.push('Q');
+        }
+        if sides_str.is_empty() {
+            sides_str.push('-');
+        }
+
+        serializer.serialize_str(&sides_str)
+    }
+}
+
+impl<'de> Deserialize<'de> for CastleMoves {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, |value| match value {
+            "K" => Ok(CastleMoves::KingSide),
+            "Q" => Ok(CastleMoves::QueenSide),
+            "KQ" => Ok(CastleMoves::all()),
+            "-" => Ok(CastleMoves::none()),
+            _ => Err(ValidationError::Parse{token: value.to_string()})
+        })
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_str(self.to_fen(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, |value| value.to_fen().to_board())
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut move_str = format!("{}{}", self.piece.to_fen(), self.from.to_alg());
+        if let Some(taken) = &self.taken {
+            move_str.push_str(&format!("x{}", taken.to_fen()));
+        }
+        move_str.push_str(&self.to.to_alg().to_string());
+        if let Some(promotion) = self.promotion {
+            move_str.push_str(&format!("={}", promotion.to_alg()));
+        }
+        if let Some((rook_from, rook_to)) = &self.castle {
+            move_str.push_str(&format!("/{}{}", rook_from.to_alg(), rook_to.to_alg()));
+        }
+
+        serializer.serialize_str(&move_str)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, parse_move)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateRepr {
+    fen: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    initial: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    moves: Vec<String>
+}
+
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (initial, moves) = match self.move_history.is_empty() {
+            true => (None, Vec::new()),
+            false => (
+                Some(self.initial_state().to_fen().to_string()),
+                self.move_history.iter().map(|m| m.to_uci().to_string()).collect()
+            )
+        };
+
+        StateRepr{fen: self.to_fen().to_string(), initial, moves}.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = StateRepr::deserialize(deserializer)?;
+
+        parse_state(repr).map_err(D::Error::custom)
+    }
+}
+
+fn parse_piece(value: &str) -> Result<Piece, ValidationError> {
+    match value.len() {
+        1 => value.to_fen().to_piece(),
+        _ => Err(ValidationError::Parse{token: value.to_string()})
+    }
+}
+
+fn parse_position(value: &str) -> Result<Position, ValidationError> {
+    let position = value.to_alg().to_position()?;
+
+    match position.is_valid() && value.len() == 2 {
+        true => Ok(position),
+        false => Err(ValidationError::OffBoard{position})
+    }
+}
+
+/// Parse a move in the form of `MOVE_RE`, checking its parts are consistent with each other,
+/// though not with any position.
+fn parse_move(value: &str) -> Result<Move, ValidationError> {
+    let parse_error = || ValidationError::Parse{token: value.to_string()};
+    let captures = MOVE_RE.captures(value).ok_or_else(parse_error)?;
+
+    let piece = parse_piece(&captures[1])?;
+    let from = parse_position(&captures[2])?;
+    let taken = captures.get(3).map(|m| parse_piece(m.as_str())).transpose()?;
+    let to = parse_position(&captures[4])?;
+    let promotion = captures.get(5).map(|m| m.as_str().to_alg().to_piece_type()).transpose()?;
+    let castle = match (captures.get(6), captures.get(7)) {
+        (Some(rook_from), Some(rook_to)) => Some((parse_position(rook_from.as_str())?, parse_position(rook_to.as_str())?)),
+        _ => None
+    };
+
+    let last_rank = if piece.color == Color::White { 7 } else { 0 };
+    let back_rank = 7 - last_rank;
+    let promotes = piece.piece_type == PieceType::Pawn && to.rank == last_rank;
+    let takes_own = taken.as_ref().is_some_and(|t| t.color == piece.color || t.piece_type == PieceType::King);
+    let castle_valid = match &castle {
+        Some((rook_from, rook_to)) => piece.piece_type == PieceType::King && taken.is_none() &&
+            [&from, &to, rook_from, rook_to].iter().all(|p| p.rank == back_rank),
+        None => from != to
+    };
+
+    if promotes != promotion.is_some() || takes_own || !castle_valid {
+        return Err(parse_error());
+    }
+
+    Ok(Move::new(from, to, piece, taken, promotion, castle))
+}
+
+fn parse_state(repr: StateRepr) -> Result<State, ValidationError> {
+    let initial_str = match (&repr.initial, repr.moves.is_empty()) {
+        (Some(initial_str), _) => initial_str,
+        (None, true) => return repr.fen.to_fen().to_state(),
+        (None, false) => return Err(ValidationError::InvalidState{token: repr.fen})
+    };
+
+    let mut state = initial_str.to_fen().to_state()?;
+    for move_str in &repr.moves {
+        let next_move = move_str.to_uci().to_move(&state)?;
+        state.make_move(&next_move);
+    }
+
+    match state.to_fen().to_string() == repr.fen {
+        true => Ok(state),
+        false => Err(ValidationError::InvalidState{token: repr.fen})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EndResult, EndCondition};
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> (String, T) {
+        let json = serde_json::to_string(value).unwrap();
+        let parsed = serde_json::from_str(&json).unwrap();
+
+        (json, parsed)
+    }
+
+    #[test]
+    fn test_simple_types() {
+        assert_eq!(round_trip(&Color::Black), ("\"black\"".to_string(), Color::Black));
+        assert_eq!(round_trip(&PieceType::Knight), ("\"N\"".to_string(), PieceType::Knight));
+        assert_eq!(round_trip(&Piece::new(Color::Black, PieceType::Queen)).0, "\"q\"");
+        assert_eq!(round_trip(&Position::new(3, 4)), ("\"e4\"".to_string(), Position::new(3, 4)));
+        assert_eq!(round_trip(&CastleMoves::all()), ("\"KQ\"".to_string(), CastleMoves::all()));
+        assert_eq!(round_trip(&CastleMoves::none()).0, "\"-\"");
+
+        let result = EndResult::win(Color::White, EndCondition::Checkmate);
+        assert_eq!(round_trip(&result), ("{\"condition\":\"checkmate\",\"winner\":\"white\"}".to_string(), result));
+
+        assert!(serde_json::from_str::<Color>("\"red\"").is_err());
+        assert!(serde_json::from_str::<Position>("\"i9\"").is_err());
+        assert!(serde_json::from_str::<Piece>("\"Nn\"").is_err());
+    }
+
+    #[test]
+    fn test_moves() {
+        let state = "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1".to_fen().to_state().unwrap();
+
+        for legal in state.get_legal_moves() {
+            let (_, parsed) = round_trip(&legal);
+            assert_eq!(parsed.to_uci().to_string(), legal.to_uci().to_string());
+        }
+
+        let castle = "O-O".to_alg().to_move(&state).unwrap();
+        assert_eq!(round_trip(&castle).0, "\"Ke1g1/h1f1\"");
+        let promotion = "bxa8=Q".to_alg().to_move(&state).unwrap();
+        assert_eq!(round_trip(&promotion).0, "\"Pb7xra8=Q\"");
+
+        for invalid in ["Pb7b8", "Pb6b7=Q", "Ne1xKf3", "Ne1xNf3", "Re1g1/h1f1", "Ke1e1", "Ke2g2/h2f2"] {
+            assert!(serde_json::from_str::<Move>(&format!("\"{}\"", invalid)).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_board_and_state() {
+        let board = State::default().board.clone();
+        let (json, parsed) = round_trip(&board);
+        assert_eq!(json, "\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR\"");
+        assert_eq!(parsed.to_fen().to_string(), board.to_fen().to_string());
+
+        let mut state = State::default();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            let next_move = uci.to_uci().to_move(&state).unwrap();
+            state.make_move(&next_move);
+        }
+
+        let (json, parsed) = round_trip(&state);
+        assert_eq!(parsed.key(), state.key());
+        assert_eq!(parsed.move_history.len(), 3);
+        assert!(json.contains("\"moves\":[\"e2e4\",\"e7e5\",\"g1f3\"]"));
+
+        assert_eq!(
+            serde_json::from_str::<State>("{\"fen\": \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\"}").unwrap().to_fen().to_string(),
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        );
+        assert!(serde_json::from_str::<State>("{\"fen\": \"4k3/8/8/8/8/8/8/8 w - - 0 1\"}").is_err());
+        assert!(serde_json::from_str::<State>(
+            "{\"fen\": \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\", \"initial\": \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\", \"moves\": [\"e1e2\"]}"
+        ).is_err());
+    }
+}
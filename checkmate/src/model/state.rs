@@ -1,13 +1,17 @@
+use std::sync::Arc;
+
 use readonly;
-use log::info;
 
 use super::color::Color;
 use super::position::Position;
 use super::piece_type::PieceType;
-use super::move_repr::{Move, CastleMoves};
+use super::move_repr::{Move, CastleMoves, STANDARD_ROOK_FILES};
+use super::piece::Piece;
 use super::board::Board;
+use super::bitboard::Bitboard;
 use super::end::{EndResult, EndCondition};
-use super::move_rules::compute_moves_for;
+use super::zobrist::{compute_key, piece_key, active_color_key, castles_key, en_passant_key};
+use super::move_rules::{compute_legal_moves, compute_moves_for};
 
 #[readonly::make]
 #[derive(Clone)]
@@ -15,21 +19,37 @@ pub struct State {
     pub board: Board,
     pub active_color: Color,
     pub move_history: Vec<Move>,
+    /// Plies since the last capture or pawn move.
+    pub halfmove_clock: u32,
+    /// Starts at 1 and increments after each move by black.
+    pub fullmove_number: u32,
     en_passant_target: Option<Position>,
     allowed_castles: [CastleMoves; 2],
-    lookahead: u32
+    //  Files of the rooks each castle right refers to, by color then side.
+    castle_rook_files: [[usize; 2]; 2],
+    key: u64,
+    //  Keys of the positions before this one, oldest first.
+    key_history: Vec<u64>,
+    //  The position before the first move of the history, once a move has been made.
+    origin: Option<Arc<State>>
+}
+
+/// What [`State::make_move`] overwrites, for [`State::unmake_move`] to put back.
+#[derive(Clone, Debug)]
+pub struct Undo {
+    en_passant_target: Option<Position>,
+    allowed_castles: [CastleMoves; 2],
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    key: u64
 }
 
 impl Default for State {
     fn default() -> Self {
-        Self{
-            board: Board::default(),
-            active_color: Color::White,
-            move_history: Vec::new(),
-            allowed_castles: [CastleMoves::all(), CastleMoves::all()],
-            en_passant_target: None,
-            lookahead: 0
-        }
+        Self::new(
+            Board::default(), Color::White, [CastleMoves::all(), CastleMoves::all()],
+            [STANDARD_ROOK_FILES, STANDARD_ROOK_FILES], None, (0, 1), Vec::new()
+        )
     }
 }
 
@@ -37,39 +57,116 @@ impl State {
     pub(super) fn new(
         board: Board, active_color: Color,
         allowed_castles: [CastleMoves; 2],
+        castle_rook_files: [[usize; 2]; 2],
         en_passant_target: Option<Position>,
+        (halfmove_clock, fullmove_number): (u32, u32),
         move_history: Vec<Move>
     ) -> Self {
+        let key = compute_key(&board, active_color, &allowed_castles, en_passant_target.as_ref());
+
         Self{
             board, move_history, active_color,
-            allowed_castles, en_passant_target,
-            lookahead: 0
+            halfmove_clock, fullmove_number,
+            allowed_castles, castle_rook_files, en_passant_target,
+            key, key_history: Vec::new(),
+            origin: None
         }
     }
 
-    fn color_has_king_only(&self, color: Color) -> bool {
-        let positions = self.board.positions_for(color);
+    /// Return the position before the first move of [`State::move_history`]. States built
+    /// with a history but no record of where it started are assumed to start from the
+    /// initial position.
+    pub fn initial_state(&self) -> State {
+        match (&self.origin, self.move_history.is_empty()) {
+            (Some(origin), _) => origin.as_ref().clone(),
+            (None, true) => self.clone(),
+            (None, false) => State::default()
+        }
+    }
 
-        for position in positions {
-            let piece = self.board[position].as_ref().unwrap();
+    /// Return the Zobrist key of the position, which covers piece placement, the active
+    /// color, castle rights and any en-passant capture available.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
 
-            if piece.piece_type != PieceType::King {
-                return false;
-            }
+    /// Return how many times the current position has occurred, including now. Only
+    /// positions played through [`State::next_for_move`] since the last capture or pawn
+    /// move are counted.
+    pub fn repetitions(&self) -> usize {
+        let reversible = (self.halfmove_clock as usize).min(self.key_history.len());
+
+        1 + self.key_history.iter().rev().take(reversible)
+            .skip(1).step_by(2)
+            .filter(|k| **k == self.key)
+            .count()
+    }
+
+    /// Return the knight and bishop counts of `color`, or `None` if it has anything beyond
+    /// minor pieces and its king.
+    fn minor_pieces_only(&self, color: Color) -> Option<(usize, usize)> {
+        let knights = self.board.positions_of(color, PieceType::Knight);
+        let bishops = self.board.positions_of(color, PieceType::Bishop);
+        let king = self.board.positions_of(color, PieceType::King);
+
+        match self.board.occupied_by(color) == knights | bishops | king {
+            true => Some((knights.len(), bishops.len())),
+            false => None
         }
+    }
+
+    /// Return whether neither side can checkmate by any sequence of legal moves. Covers
+    /// lone kings, a single minor piece, and any number of bishops all on one square color.
+    pub fn is_insufficient_materiel(&self) -> bool {
+        let (white, black) = match (self.minor_pieces_only(Color::White), self.minor_pieces_only(Color::Black)) {
+            (Some(white), Some(black)) => (white, black),
+            _ => return false
+        };
 
-        true
+        let (knights, bishops) = (white.0 + black.0, white.1 + black.1);
+        if knights + bishops <= 1 {
+            return true;
+        }
+
+        let all_bishops = self.board.positions_of(Color::White, PieceType::Bishop) |
+            self.board.positions_of(Color::Black, PieceType::Bishop);
+        knights == 0 && ((all_bishops & Bitboard::LIGHT_SQUARES).is_empty() || (all_bishops & Bitboard::DARK_SQUARES).is_empty())
+    }
+
+    /// Return whether `color` lacks the material to win on time under the USCF rules: a lone
+    /// king, a king and one minor piece, or a king and two knights against no pawns.
+    pub fn is_insufficient_materiel_on_time(&self, color: Color) -> bool {
+        if self.is_insufficient_materiel() {
+            return true;
+        }
+
+        match self.minor_pieces_only(color) {
+            Some((knights, bishops)) => {
+                let opponent_pawns = self.board.positions_of(!color, PieceType::Pawn);
+
+                knights + bishops <= 1 || (bishops == 0 && knights == 2 && opponent_pawns.is_empty())
+            },
+            None => false
+        }
+    }
+
+    /// Return the result when `flagged` runs out of time in this position.
+    pub fn timeout_result(&self, flagged: Color) -> EndResult {
+        match self.is_insufficient_materiel_on_time(!flagged) {
+            true => EndResult::draw(EndCondition::InsufficientMateriel),
+            false => EndResult::win(!flagged, EndCondition::Timeout)
+        }
     }
 
     pub fn check_result(&self) -> Option<EndResult> {
-        if self.color_has_king_only(Color::White) && self.color_has_king_only(Color::Black) {
+        if self.is_insufficient_materiel() {
             return Some(
                 EndResult::draw(EndCondition::InsufficientMateriel)
             );
         }
 
         let moves = self.get_legal_moves();
-        if moves.len() == 0 {
+        if moves.is_empty() {
             if self.is_check_against(self.active_color) {
                 return Some(
                     EndResult::win(!self.active_color, EndCondition::Checkmate)
@@ -81,6 +178,37 @@ impl State {
             );
         }
 
+        if self.repetitions() >= 5 {
+            return Some(
+                EndResult::draw(EndCondition::FivefoldRepetition)
+            );
+        }
+
+        //  Checked after checkmate, which takes precedence on the seventy-fifth move.
+        if self.halfmove_clock >= 150 {
+            return Some(
+                EndResult::draw(EndCondition::SeventyFiveMoveRule)
+            );
+        }
+
+        None
+    }
+
+    /// Return the draw the active player could claim in this position, if any. Unlike
+    /// [`State::check_result`], these don't end the game on their own.
+    pub fn claimable_result(&self) -> Option<EndResult> {
+        if self.repetitions() >= 3 {
+            return Some(
+                EndResult::draw(EndCondition::ThreefoldRepetition)
+            );
+        }
+
+        if self.halfmove_clock >= 100 {
+            return Some(
+                EndResult::draw(EndCondition::FiftyMoveRule)
+            );
+        }
+
         None
     }
 
@@ -94,80 +222,274 @@ impl State {
         self.allowed_castles[color_idx]
     }
 
+    /// Return the file of the rook `color` castles with on `side`, whether or not the right
+    /// is still held.
+    pub fn get_castle_rook_file(&self, color: Color, side: CastleMoves) -> usize {
+        let color_idx: usize = color.into();
+
+        self.castle_rook_files[color_idx][side.side_index()]
+    }
+
     pub fn next_for_move(&self, next_move: &Move) -> State {
-        let mut new_history = self.move_history.clone();
-        new_history.push(next_move.clone());
+        let mut next = self.clone();
+        next.make_move(next_move);
 
-        let new_board = self.board.next_for_move(
-            &next_move.from, &next_move.to, &next_move.promotion,
-            &next_move.castle
-        );
+        next
+    }
+
+    /// Play `next_move` in place, returning what [`State::unmake_move`] needs to take it back.
+    pub fn make_move(&mut self, next_move: &Move) -> Undo {
+        if self.origin.is_none() && self.move_history.is_empty() {
+            self.origin = Some(Arc::new(self.clone()));
+        }
+
+        let undo = Undo{
+            en_passant_target: self.en_passant_target.clone(),
+            allowed_castles: self.allowed_castles,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            key: self.key
+        };
 
         let color_idx: usize = next_move.piece.color.into();
-        
-        let mut new_allowed_castles = self.allowed_castles.clone();
-        if let Some(disallowed) = next_move.disallowed_castle() {
-            new_allowed_castles[color_idx] = new_allowed_castles[color_idx].and(disallowed.not());
+        let taken_position = self.taken_position(next_move, self.en_passant_target.as_ref());
+
+        let mut key = self.key
+            ^ active_color_key(self.active_color) ^ active_color_key(!self.active_color)
+            ^ en_passant_key(&self.board, self.active_color, self.en_passant_target.as_ref());
+
+        let placed = match next_move.promotion {
+            Some(promotion) => Piece::new(next_move.piece.color, promotion),
+            None => next_move.piece.clone()
+        };
+        key ^= piece_key(&next_move.piece, next_move.from.index()) ^ piece_key(&placed, next_move.to.index());
+
+        if let Some(taken) = &next_move.taken {
+            key ^= piece_key(taken, taken_position.index());
+        }
+        if let Some((rook_from, rook_to)) = &next_move.castle {
+            let rook = Piece::new(next_move.piece.color, PieceType::Rook);
+            key ^= piece_key(&rook, rook_from.index()) ^ piece_key(&rook, rook_to.index());
+        }
+
+        self.board.apply_move(&next_move.from, &next_move.to, &next_move.promotion, &next_move.castle);
+        //  En-passant takes the pawn beside the origin rather than at the destination.
+        if taken_position != next_move.to {
+            self.board.take(&taken_position);
         }
 
-        let mut new_en_passant: Option<Position> = None;
+        key ^= castles_key(&self.allowed_castles);
+        if let Some(disallowed) = next_move.disallowed_castle(&self.castle_rook_files[color_idx]) {
+            self.allowed_castles[color_idx] = self.allowed_castles[color_idx].and(disallowed.not());
+        }
+        if let Some(disallowed) = next_move.disallowed_opponent_castle(&self.castle_rook_files[1 - color_idx]) {
+            self.allowed_castles[1 - color_idx] = self.allowed_castles[1 - color_idx].and(disallowed.not());
+        }
+        key ^= castles_key(&self.allowed_castles);
+
+        self.en_passant_target = None;
         if next_move.piece.piece_type == PieceType::Pawn {
             let forward_one = next_move.from.forward(next_move.piece.color);
             if forward_one.forward(next_move.piece.color) == next_move.to {
-                new_en_passant = Some(forward_one);
+                self.en_passant_target = Some(forward_one);
             }
         }
+        key ^= en_passant_key(&self.board, !self.active_color, self.en_passant_target.as_ref());
 
-        State{
-            board: new_board,
-            move_history: new_history,
-            active_color: !self.active_color,
-            allowed_castles: new_allowed_castles,
-            en_passant_target: new_en_passant,
-            lookahead: if self.lookahead > 0 { self.lookahead + 1 } else { 0 }
+        if next_move.piece.piece_type == PieceType::Pawn || next_move.taken.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if self.active_color == Color::Black {
+            self.fullmove_number += 1;
         }
+
+        self.key_history.push(self.key);
+        self.key = key;
+        self.move_history.push(next_move.clone());
+        self.active_color = !self.active_color;
+
+        undo
     }
 
-    pub fn is_check_against(&self, color: Color) -> bool {
-        if self.lookahead >= 2 {
-            return false;
+    /// Take back the last move played with [`State::make_move`], given the [`Undo`] it returned.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let last_move = self.move_history.pop().expect("no move to unmake");
+        let taken_position = self.taken_position(&last_move, undo.en_passant_target.as_ref());
+
+        self.board.unapply_move(
+            &last_move.from, &last_move.to, &last_move.piece,
+            last_move.taken.as_ref().map(|taken| (taken, &taken_position)),
+            &last_move.castle
+        );
+
+        self.active_color = !self.active_color;
+        self.en_passant_target = undo.en_passant_target;
+        self.allowed_castles = undo.allowed_castles;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.key = undo.key;
+        self.key_history.pop();
+    }
+
+    fn taken_position(&self, next_move: &Move, en_passant_target: Option<&Position>) -> Position {
+        if next_move.piece.piece_type == PieceType::Pawn && en_passant_target == Some(&next_move.to) {
+            return Position::new(next_move.from.rank, next_move.to.file);
         }
 
-        let mut lookahead = self.clone();
-        lookahead.lookahead = 1;
-        let check_moves = lookahead.get_legal_moves_for(!color);
+        next_move.to.clone()
+    }
 
-        for check_move in check_moves {
-            if let Some(piece) = &check_move.taken {
-                if piece.piece_type == PieceType::King {
-                    return true;
-                }
+    /// Return whether any piece of `by_color` attacks `position`.
+    pub fn is_square_attacked(&self, position: &Position, by_color: Color) -> bool {
+        !(self.attackers_of(position) & self.board.occupied_by(by_color)).is_empty()
+    }
+
+    /// Return the positions of every piece, of either color, attacking `position`.
+    pub fn attackers_of(&self, position: &Position) -> Bitboard {
+        self.board.attackers_to(position.index(), self.board.occupied())
+    }
+
+    /// Return the positions of the pieces giving check to the active color.
+    pub fn checkers(&self) -> Bitboard {
+        match self.board.king_position(self.active_color) {
+            Some(king) => self.attackers_of(&king) & self.board.occupied_by(!self.active_color),
+            None => Bitboard::EMPTY
+        }
+    }
+
+    /// Return the material `next_move` wins in centipawns for the side moving, or loses if
+    /// negative, once both sides have recaptured on its destination with their least
+    /// valuable piece for as long as it pays. Pins are ignored.
+    pub fn see(&self, next_move: &Move) -> i32 {
+        if next_move.castle.is_some() {
+            return 0;
+        }
+
+        let value = |piece_type: PieceType| piece_type.materiel_value() as i32 * 100;
+        let target = next_move.to.index();
+
+        let mut occupied = self.board.occupied() ^ Bitboard::from_position(&next_move.from);
+        if next_move.piece.piece_type == PieceType::Pawn && self.en_passant_target.as_ref() == Some(&next_move.to) {
+            occupied ^= Bitboard::from_position(&Position::new(next_move.from.rank, next_move.to.file));
+        }
+
+        //  gains[n] is what the side making capture n stands to win if the exchange stops
+        //  after it.
+        let mut gains = vec![next_move.taken.as_ref().map_or(0, |taken| value(taken.piece_type))];
+        let mut on_target = match next_move.promotion {
+            Some(promotion) => {
+                gains[0] += value(promotion) - value(PieceType::Pawn);
+                value(promotion)
+            },
+            None => value(next_move.piece.piece_type)
+        };
+
+        let mut color = !next_move.piece.color;
+        loop {
+            let attackers = self.board.attackers_to(target, occupied) & occupied;
+            let least_valuable = [
+                PieceType::Pawn, PieceType::Knight, PieceType::Bishop,
+                PieceType::Rook, PieceType::Queen, PieceType::King
+            ].into_iter().find_map(|piece_type| {
+                (attackers & self.board.positions_of(color, piece_type)).first().map(|position| (piece_type, position))
+            });
+            let (piece_type, position) = match least_valuable {
+                Some(found) => found,
+                None => break
+            };
+
+            occupied ^= Bitboard::from_position(&position);
+            //  The king can only recapture once the other side has run out of attackers.
+            if piece_type == PieceType::King && !(self.board.attackers_to(target, occupied) & occupied & self.board.occupied_by(!color)).is_empty() {
+                break;
             }
+
+            gains.push(on_target - gains.last().unwrap());
+            on_target = value(piece_type);
+            color = !color;
+        }
+
+        //  Each side stops capturing once continuing would lose it material.
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.last_mut().unwrap();
+            *previous = -(-*previous).max(last);
         }
 
-        false
+        gains[0]
+    }
+
+    pub fn is_check_against(&self, color: Color) -> bool {
+        match self.board.king_position(color) {
+            Some(king) => self.is_square_attacked(&king, !color),
+            None => false
+        }
+    }
+
+    /// Count the leaf nodes of the legal move tree `depth` plies deep. Comparing the count
+    /// against published results is the standard check of move generation.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        self.clone().perft_in_place(depth)
+    }
+
+    fn perft_in_place(&mut self, depth: u32) -> u64 {
+        let moves = self.get_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves.iter().map(|m| {
+            let undo = self.make_move(m);
+            let count = self.perft_in_place(depth - 1);
+            self.unmake_move(undo);
+
+            count
+        }).sum()
+    }
+
+    /// Break [`State::perft`] down by root move, for narrowing down where a count diverges.
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut scratch = self.clone();
+        self.get_legal_moves().into_iter().map(|m| {
+            let undo = scratch.make_move(&m);
+            let count = scratch.perft(depth - 1);
+            scratch.unmake_move(undo);
+
+            (m, count)
+        }).collect()
     }
 
     pub fn get_legal_moves(&self) -> Vec<Move> {
         self.get_legal_moves_for(self.active_color)
     }
     
-    pub fn get_legal_moves_for(&self, color: Color) -> Vec<Move> {
-        let piece_positions = self.board.positions_for(color);
-
-        let mut moves: Vec<Move> = Vec::with_capacity(16);
-        for position in piece_positions {
-            moves.extend(compute_moves_for(self, &position));
+    /// Return the legal moves of the piece at `position`, if there is one.
+    pub fn get_legal_moves_from(&self, position: &Position) -> Vec<Move> {
+        match self.board[position] {
+            Some(_) => compute_moves_for(self, position),
+            None => Vec::new()
         }
+    }
 
-        moves
+    pub fn get_legal_moves_for(&self, color: Color) -> Vec<Move> {
+        compute_legal_moves(self, color)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::piece::Piece;
+    use crate::formats::{ToFEN, ToState, ToAlg, ToMove};
 
     #[test]
     fn test_initial_state() {
@@ -182,4 +504,265 @@ mod tests {
 
         assert_eq!(state.get_legal_moves().len(), 20);
     }
+
+    #[test]
+    fn test_attack_queries() {
+        let state = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_fen().to_state().unwrap();
+
+        let queen = Position::new(3, 7);
+        assert_eq!(state.checkers().into_iter().collect::<Vec<Position>>(), vec![queen.clone()]);
+        assert!(state.is_check_against(Color::White));
+        assert!(!state.is_check_against(Color::Black));
+
+        //  The g4 pawn is attacked by the queen and defended by the f3 pawn.
+        let pawn = Position::new(3, 6);
+        assert!(state.is_square_attacked(&pawn, Color::Black));
+        assert!(state.is_square_attacked(&pawn, Color::White));
+        assert_eq!(
+            state.attackers_of(&pawn).into_iter().collect::<Vec<Position>>(),
+            vec![Position::new(2, 5), queen]
+        );
+        assert!(!state.is_square_attacked(&Position::new(4, 0), Color::White));
+
+        assert_eq!(state.check_result(), Some(EndResult::win(Color::Black, EndCondition::Checkmate)));
+    }
+
+    #[test]
+    fn test_see() {
+        let see = |fen: &str, san: &str| {
+            let state = fen.to_fen().to_state().unwrap();
+
+            state.see(&san.to_alg().to_move(&state).unwrap())
+        };
+
+        assert_eq!(see("4k3/8/8/3p4/8/8/3Q4/4K3 w - - 0 1", "Qxd5"), 100);
+        assert_eq!(see("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "Qxd5"), -800);
+        assert_eq!(see("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "Qd3"), 0);
+
+        //  The rook behind joins the exchange once the one in front has gone.
+        assert_eq!(see("4k3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "Rxd5"), 100);
+        assert_eq!(see("4k3/3r4/8/3p4/8/8/3R4/4K3 w - - 0 1", "Rxd5"), -400);
+
+        //  The king can't recapture onto a square the bishop still covers.
+        assert_eq!(see("4k3/4r3/8/8/8/8/4P3/4K3 b - - 0 1", "Rxe2+"), -400);
+        assert_eq!(see("4k3/4r3/8/7b/8/8/4P3/4K3 b - - 0 1", "Rxe2+"), 100);
+
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"), 100);
+        assert_eq!(see("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1", "exd8=Q+"), 1300);
+        assert_eq!(see("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e8=Q"), -100);
+    }
+
+    fn play(state: &State, from: Position, to: Position) -> State {
+        let next_move = state.get_legal_moves_from(&from).into_iter().find(|m| m.to == to).unwrap();
+
+        state.next_for_move(&next_move)
+    }
+
+    #[test]
+    fn test_clocks() {
+        let mut state = "4k3/8/8/8/8/8/4P3/4K2R b K - 98 60".to_fen().to_state().unwrap();
+
+        state = play(&state, Position::new(7, 4), Position::new(7, 3));
+        assert_eq!((state.halfmove_clock, state.fullmove_number), (99, 61));
+        assert_eq!(state.claimable_result(), None);
+
+        state = play(&state, Position::new(0, 7), Position::new(0, 6));
+        assert_eq!((state.halfmove_clock, state.fullmove_number), (100, 61));
+        assert_eq!(state.claimable_result(), Some(EndResult::draw(EndCondition::FiftyMoveRule)));
+        assert_eq!(state.check_result(), None);
+
+        state = play(&state, Position::new(7, 3), Position::new(7, 4));
+        state = play(&state, Position::new(1, 4), Position::new(3, 4));
+        assert_eq!((state.halfmove_clock, state.fullmove_number), (0, 62));
+        assert_eq!(state.claimable_result(), None);
+    }
+
+    #[test]
+    fn test_seventy_five_move_rule() {
+        let state = "4k3/8/8/8/8/8/8/4K2R w K - 150 120".to_fen().to_state().unwrap();
+        assert_eq!(state.check_result(), Some(EndResult::draw(EndCondition::SeventyFiveMoveRule)));
+
+        //  Checkmate delivered on the seventy-fifth move stands.
+        let state = "R3k3/8/4K3/8/8/8/8/8 b - - 150 120".to_fen().to_state().unwrap();
+        assert_eq!(state.check_result(), Some(EndResult::win(Color::White, EndCondition::Checkmate)));
+    }
+
+    #[test]
+    fn test_incremental_key() {
+        //  Covers castles, promotions with capture and en-passant captures two plies deep.
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+        ];
+
+        for fen in fens {
+            let state = fen.to_fen().to_state().unwrap();
+            for first in state.get_legal_moves() {
+                let next = state.next_for_move(&first);
+                for second in next.get_legal_moves() {
+                    let last = next.next_for_move(&second);
+
+                    assert_eq!(
+                        last.key(),
+                        compute_key(&last.board, last.active_color, &last.allowed_castles, last.en_passant_target.as_ref())
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_insufficient_materiel() {
+        let insufficient = |fen: &str| fen.to_fen().to_state().unwrap().is_insufficient_materiel();
+
+        assert!(insufficient("8/8/4k3/8/8/3K4/8/8 w - - 0 1"));
+        assert!(insufficient("8/8/4k3/8/8/3K4/8/5B2 w - - 0 1"));
+        assert!(insufficient("8/8/4k3/8/8/3K4/8/5n2 w - - 0 1"));
+        //  Bishops on f1 and c8 are both light squared.
+        assert!(insufficient("2b5/8/4k3/8/8/3K4/8/5B2 w - - 0 1"));
+        assert!(insufficient("2b5/8/4k3/8/8/3K4/6B1/5B2 w - - 0 1"));
+
+        assert!(!insufficient("1b6/8/4k3/8/8/3K4/8/5B2 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/8/3K4/8/4NN2 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/8/3K4/8/4Nn2 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/8/3K4/4P3/8 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/8/3K4/8/R7 w - - 0 1"));
+
+        let state = "8/8/4k3/8/8/3K4/8/5B2 b - - 0 1".to_fen().to_state().unwrap();
+        assert_eq!(state.check_result(), Some(EndResult::draw(EndCondition::InsufficientMateriel)));
+    }
+
+    #[test]
+    fn test_timeout_result() {
+        let timeout = |fen: &str, flagged: Color| fen.to_fen().to_state().unwrap().timeout_result(flagged);
+
+        //  A lone knight can't win on time, even though it could mate with help from pawns.
+        assert_eq!(
+            timeout("8/8/4k3/4p3/8/3K4/8/4N3 w - - 0 1", Color::Black),
+            EndResult::draw(EndCondition::InsufficientMateriel)
+        );
+        assert_eq!(
+            timeout("8/8/4k3/4p3/8/3K4/8/4N3 w - - 0 1", Color::White),
+            EndResult::win(Color::Black, EndCondition::Timeout)
+        );
+
+        //  Two knights only win on time against pawns.
+        assert_eq!(
+            timeout("8/8/4k3/8/8/3K4/8/4NN2 w - - 0 1", Color::Black),
+            EndResult::draw(EndCondition::InsufficientMateriel)
+        );
+        assert_eq!(
+            timeout("8/8/4k3/4p3/8/3K4/8/4NN2 w - - 0 1", Color::Black),
+            EndResult::win(Color::White, EndCondition::Timeout)
+        );
+
+        assert_eq!(
+            timeout("8/8/4k3/8/8/3K4/8/R7 w - - 0 1", Color::Black),
+            EndResult::win(Color::White, EndCondition::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_origin_state() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 30";
+        let mut state = fen.to_fen().to_state().unwrap();
+        assert_eq!(state.initial_state().to_fen().to_string(), fen);
+
+        state = play(&state, Position::new(1, 4), Position::new(3, 4));
+        state = play(&state, Position::new(7, 4), Position::new(6, 4));
+        assert_eq!(state.initial_state().to_fen().to_string(), fen);
+        assert!(state.initial_state().move_history.is_empty());
+    }
+
+    #[test]
+    fn test_make_unmake() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 20",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+        ];
+        let snapshot = |state: &State| (
+            state.to_fen().to_string(), state.key(), state.key_history.clone(),
+            state.move_history.len(), state.board.occupied(), state.board.occupied_by(Color::White)
+        );
+
+        for fen in fens {
+            let mut state = fen.to_fen().to_state().unwrap();
+            let before = snapshot(&state);
+
+            for first in state.get_legal_moves() {
+                let expected = state.next_for_move(&first);
+                let first_undo = state.make_move(&first);
+                assert_eq!(snapshot(&state), snapshot(&expected));
+
+                for second in state.get_legal_moves() {
+                    let undo = state.make_move(&second);
+                    state.unmake_move(undo);
+                }
+                assert_eq!(snapshot(&state), snapshot(&expected));
+
+                state.unmake_move(first_undo);
+                assert_eq!(snapshot(&state), before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_transposition() {
+        let (knight_b, knight_g) = ((Position::new(0, 1), Position::new(2, 2)), (Position::new(0, 6), Position::new(2, 5)));
+        let (pawn_a, pawn_h) = ((Position::new(6, 0), Position::new(5, 0)), (Position::new(6, 7), Position::new(5, 7)));
+
+        let mut first = State::default();
+        for (from, to) in [knight_b.clone(), pawn_a.clone(), knight_g.clone(), pawn_h.clone()] {
+            first = play(&first, from, to);
+        }
+        let mut second = State::default();
+        for (from, to) in [knight_g, pawn_h, knight_b, pawn_a] {
+            second = play(&second, from, to);
+        }
+
+        assert_eq!(first.key(), second.key());
+        assert_ne!(first.key(), State::default().key());
+
+        //  An en-passant target no pawn can take on is not part of the position.
+        let pushed = play(&State::default(), Position::new(1, 4), Position::new(3, 4));
+        let written = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".to_fen().to_state().unwrap();
+        assert_eq!(pushed.key(), written.key());
+
+        let capturable = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_fen().to_state().unwrap();
+        let written = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".to_fen().to_state().unwrap();
+        assert_ne!(capturable.key(), written.key());
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut state = State::default();
+        let shuffle = [
+            (Position::new(0, 6), Position::new(2, 5)), (Position::new(7, 6), Position::new(5, 5)),
+            (Position::new(2, 5), Position::new(0, 6)), (Position::new(5, 5), Position::new(7, 6))
+        ];
+
+        for (expected, result) in [(2, None), (3, Some(EndResult::draw(EndCondition::ThreefoldRepetition)))] {
+            for (from, to) in shuffle.clone() {
+                state = play(&state, from, to);
+            }
+
+            assert_eq!(state.repetitions(), expected);
+            assert_eq!(state.claimable_result(), result);
+            assert_eq!(state.check_result(), None);
+        }
+
+        for _ in 0..2 {
+            for (from, to) in shuffle.clone() {
+                state = play(&state, from, to);
+            }
+        }
+        assert_eq!(state.repetitions(), 5);
+        assert_eq!(state.check_result(), Some(EndResult::draw(EndCondition::FivefoldRepetition)));
+
+        //  A pawn move makes earlier positions unreachable.
+        state = play(&state, Position::new(1, 0), Position::new(2, 0));
+        assert_eq!(state.repetitions(), 1);
+    }
 }
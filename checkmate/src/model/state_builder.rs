@@ -1,15 +1,30 @@
 use super::color::Color;
 use super::position::Position;
-use super::move_repr::{Move, CastleMoves};
+use super::piece_type::PieceType;
+use super::piece::Piece;
+use super::move_repr::{Move, CastleMoves, STANDARD_ROOK_FILES};
+use super::bitboard::Bitboard;
+use super::board::Board;
 use super::state::State;
 use super::board_builder::BoardBuilder;
+use crate::errors::ValidationError;
+
+const BACK_RANKS: Bitboard = Bitboard(0xff000000000000ff);
 
 pub struct StateBuilder {
     board: BoardBuilder,
     move_history: Vec<Move>,
     active_color: Option<Color>,
     allowed_castles: Option<[CastleMoves; 2]>,
+    castle_rook_files: [[usize; 2]; 2],
     en_passant_target: Option<Option<Position>>,
+    clocks: Option<(u32, u32)>
+}
+
+impl Default for StateBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StateBuilder {
@@ -19,7 +34,9 @@ impl StateBuilder {
             move_history: Vec::new(),
             active_color: None,
             allowed_castles: None,
-            en_passant_target: None
+            castle_rook_files: [STANDARD_ROOK_FILES, STANDARD_ROOK_FILES],
+            en_passant_target: None,
+            clocks: None
         }
     }
 
@@ -41,6 +58,17 @@ impl StateBuilder {
         self.en_passant_target = Some(en_passant_target);
     }
 
+    /// Set the file of the rook `color` castles with on `side`, for Chess960 positions.
+    pub fn set_castle_rook_file(&mut self, color: Color, side: CastleMoves, file: usize) {
+        let color_idx: usize = color.into();
+
+        self.castle_rook_files[color_idx][side.side_index()] = file;
+    }
+
+    pub fn set_clocks(&mut self, halfmove_clock: u32, fullmove_number: u32) {
+        self.clocks = Some((halfmove_clock, fullmove_number));
+    }
+
     fn infer_active_color(&self) -> Color {
         match self.move_history.last() {
             Some(m) => !m.piece.color,
@@ -52,17 +80,45 @@ impl StateBuilder {
         let mut allowed = [CastleMoves::all(), CastleMoves::all()];
 
         for check_move in &self.move_history {
-            if let Some(mask) = check_move.disallowed_castle() {
-                let color_idx: usize = check_move.piece.color.into();
+            let color_idx: usize = check_move.piece.color.into();
 
-                allowed[color_idx] = allowed[color_idx].and(mask.not());   
+            if let Some(mask) = check_move.disallowed_castle(&self.castle_rook_files[color_idx]) {
+                allowed[color_idx] = allowed[color_idx].and(mask.not());
+            }
+            if let Some(mask) = check_move.disallowed_opponent_castle(&self.castle_rook_files[1 - color_idx]) {
+                allowed[1 - color_idx] = allowed[1 - color_idx].and(mask.not());
             }
         }
 
         allowed
     }
 
-    pub fn build(self) -> State {
+    fn infer_clocks(&self) -> (u32, u32) {
+        let halfmove_clock = self.move_history.iter().rev().take_while(|m| {
+            m.piece.piece_type != PieceType::Pawn && m.taken.is_none()
+        }).count();
+
+        (halfmove_clock as u32, (self.move_history.len() / 2) as u32 + 1)
+    }
+
+    pub fn build(mut self) -> State {
+        let board = std::mem::take(&mut self.board).build();
+
+        self.build_on(board)
+    }
+
+    /// Build the state, failing with the specific [`ValidationError`] if the position
+    /// could not arise in a legal game.
+    pub fn try_build(mut self) -> Result<State, ValidationError> {
+        let board = std::mem::take(&mut self.board).try_build()?;
+        let state = self.build_on(board);
+
+        validate(&state)?;
+
+        Ok(state)
+    }
+
+    fn build_on(self, board: Board) -> State {
         let active_color = match self.active_color {
             Some(c) => c,
             None => self.infer_active_color()
@@ -72,6 +128,131 @@ impl StateBuilder {
             None => self.infer_allowed_castles()
         };
 
-        State::new(self.board.build(), active_color, allowed_castles, None, self.move_history)
+        let clocks = match self.clocks {
+            Some(c) => c,
+            None => self.infer_clocks()
+        };
+        let en_passant_target = self.en_passant_target.flatten();
+
+        State::new(
+            board, active_color, allowed_castles, self.castle_rook_files, en_passant_target,
+            clocks, self.move_history
+        )
+    }
+}
+
+fn validate(state: &State) -> Result<(), ValidationError> {
+    for color in [Color::White, Color::Black] {
+        let count = state.board.positions_of(color, PieceType::King).len();
+        if count != 1 {
+            return Err(ValidationError::KingCount{color, count});
+        }
+    }
+
+    let pawns = state.board.positions_of(Color::White, PieceType::Pawn) | state.board.positions_of(Color::Black, PieceType::Pawn);
+    if let Some(position) = (pawns & BACK_RANKS).first() {
+        return Err(ValidationError::PawnOnBackRank{position});
+    }
+
+    if state.is_check_against(!state.active_color) {
+        return Err(ValidationError::InactiveColorInCheck{color: !state.active_color});
+    }
+
+    if let Some(target) = state.get_en_passant_position() {
+        //  The opponent's pawn must have just passed over the target from its start square.
+        let mover = !state.active_color;
+        let target_rank = if mover == Color::White { 2 } else { 5 };
+        let pushed = state.board[&target.forward(mover)].as_ref();
+
+        if target.rank != target_rank || state.board[target].is_some() ||
+            state.board[&target.forward(state.active_color)].is_some() ||
+            pushed != Some(&Piece::new(mover, PieceType::Pawn)) {
+            return Err(ValidationError::InvalidEnPassant{position: target.clone()});
+        }
+    }
+
+    //  The king may start on any file between its castling rooks, as in Chess960.
+    for color in [Color::White, Color::Black] {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        let king_position = state.board.positions_of(color, PieceType::King).first();
+        let king_home = king_position.as_ref().is_some_and(|p| p.rank == back_rank);
+
+        for castle in [CastleMoves::KingSide, CastleMoves::QueenSide] {
+            if !state.get_allowed_castles(color).contains(castle) {
+                continue;
+            }
+
+            let rook_file = state.get_castle_rook_file(color, castle);
+            let rook_home = state.board[&Position::new(back_rank, rook_file)] == Some(Piece::new(color, PieceType::Rook));
+            let rook_outside = king_position.as_ref().is_some_and(|p| match castle == CastleMoves::KingSide {
+                true => rook_file > p.file,
+                false => rook_file < p.file
+            });
+            if !(king_home && rook_home && rook_outside) {
+                return Err(ValidationError::InvalidCastleRights{color, castle});
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{ToFEN, ToState};
+
+    fn error_of(fen: &str) -> ValidationError {
+        fen.to_fen().to_state().err().unwrap()
+    }
+
+    #[test]
+    fn test_occupied_square() {
+        let mut builder = StateBuilder::new();
+        builder.board_builder().place_piece(Piece::new(Color::White, PieceType::King), Position::new(0, 4));
+        builder.board_builder().place_piece(Piece::new(Color::Black, PieceType::King), Position::new(7, 4));
+        builder.board_builder().place_piece(Piece::new(Color::White, PieceType::Rook), Position::new(0, 0));
+        builder.board_builder().place_piece(Piece::new(Color::Black, PieceType::Rook), Position::new(0, 0));
+
+        assert_eq!(builder.try_build().err(), Some(ValidationError::OccupiedSquare{position: Position::new(0, 0)}));
+    }
+
+    #[test]
+    fn test_invalid_positions() {
+        assert_eq!(error_of("8/8/8/8/8/8/8/4K3 w - - 0 1"), ValidationError::KingCount{color: Color::Black, count: 0});
+        assert_eq!(error_of("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"), ValidationError::KingCount{color: Color::White, count: 2});
+        assert_eq!(error_of("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"), ValidationError::PawnOnBackRank{position: Position::new(7, 7)});
+        assert_eq!(error_of("4k2R/8/8/8/8/8/8/4K3 w - - 0 1"), ValidationError::InactiveColorInCheck{color: Color::Black});
+    }
+
+    #[test]
+    fn test_invalid_history() {
+        //  No pawn on e4 to have passed over e3.
+        assert_eq!(
+            error_of("4k3/8/8/8/8/8/4P3/4K3 b - e3 0 1"),
+            ValidationError::InvalidEnPassant{position: Position::new(2, 4)}
+        );
+        assert_eq!(
+            error_of("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"),
+            ValidationError::InvalidEnPassant{position: Position::new(2, 4)}
+        );
+        assert!("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1".to_fen().to_state().is_ok());
+
+        assert_eq!(
+            error_of("4k3/8/8/8/8/8/8/R3K3 w KQ - 0 1"),
+            ValidationError::InvalidCastleRights{color: Color::White, castle: CastleMoves::KingSide}
+        );
+        assert_eq!(
+            error_of("3k3r/8/8/8/8/8/8/4K3 w q - 0 1"),
+            ValidationError::InvalidCastleRights{color: Color::Black, castle: CastleMoves::QueenSide}
+        );
+    }
+
+    #[test]
+    fn test_permissive_build() {
+        let mut builder = StateBuilder::new();
+        builder.board_builder().place_piece(Piece::new(Color::White, PieceType::Pawn), Position::new(0, 0));
+
+        assert_eq!(builder.build().board.occupied().len(), 1);
     }
 }
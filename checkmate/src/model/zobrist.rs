@@ -0,0 +1,162 @@
+use super::color::Color;
+use super::position::Position;
+use super::piece_type::PieceType;
+use super::piece::Piece;
+use super::move_repr::CastleMoves;
+use super::board::Board;
+use super::attacks::pawn_attacks;
+
+//  Zobrist keys: one random number per (piece, square), castle right, en-passant file and
+//  for black to move. A position's key is the XOR of the numbers for every feature present,
+//  so a move updates it by XOR-ing out what it removes and XOR-ing in what it adds.
+//
+//  The numbers come from a fixed-seed generator evaluated at compile time, so keys are
+//  stable across runs and builds.
+
+struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    castles: [[u64; 2]; 2],
+    en_passant: [u64; 8],
+    black_to_move: u64
+}
+
+const fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> ZobristKeys {
+    let mut state = 0x636865636b6d6174;
+    let mut keys = ZobristKeys{
+        pieces: [[[0; 64]; 6]; 2],
+        castles: [[0; 2]; 2],
+        en_passant: [0; 8],
+        black_to_move: 0
+    };
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece_type = 0;
+        while piece_type < 6 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[color][piece_type][square] = split_mix(&mut state);
+                square += 1;
+            }
+            piece_type += 1;
+        }
+
+        keys.castles[color][0] = split_mix(&mut state);
+        keys.castles[color][1] = split_mix(&mut state);
+        color += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        keys.en_passant[file] = split_mix(&mut state);
+        file += 1;
+    }
+
+    keys.black_to_move = split_mix(&mut state);
+
+    keys
+}
+
+static KEYS: ZobristKeys = generate_keys();
+
+pub(super) fn piece_key(piece: &Piece, square: usize) -> u64 {
+    let color_idx: usize = piece.color.into();
+    let type_idx: usize = piece.piece_type.into();
+
+    KEYS.pieces[color_idx][type_idx][square]
+}
+
+pub(super) fn active_color_key(active_color: Color) -> u64 {
+    match active_color {
+        Color::White => 0,
+        Color::Black => KEYS.black_to_move
+    }
+}
+
+pub(super) fn castles_key(allowed_castles: &[CastleMoves; 2]) -> u64 {
+    let mut key = 0;
+    for (color_idx, allowed) in allowed_castles.iter().enumerate() {
+        if allowed.contains(CastleMoves::KingSide) {
+            key ^= KEYS.castles[color_idx][0];
+        }
+        if allowed.contains(CastleMoves::QueenSide) {
+            key ^= KEYS.castles[color_idx][1];
+        }
+    }
+
+    key
+}
+
+/// The en-passant target only counts towards the key when a pawn of the active color could
+/// actually take on it, since otherwise the positions are the same for repetition purposes.
+pub(super) fn en_passant_key(board: &Board, active_color: Color, target: Option<&Position>) -> u64 {
+    let target = match target {
+        Some(t) => t,
+        None => return 0
+    };
+
+    //  Squares from which an active pawn attacks the target are those the target would
+    //  attack as an opponent pawn.
+    let capturers = pawn_attacks(!active_color, target.index()) & board.positions_of(active_color, PieceType::Pawn);
+    match capturers.is_empty() {
+        true => 0,
+        false => KEYS.en_passant[target.file]
+    }
+}
+
+/// Compute the key of a position from scratch.
+pub(super) fn compute_key(
+    board: &Board, active_color: Color,
+    allowed_castles: &[CastleMoves; 2], en_passant_target: Option<&Position>
+) -> u64 {
+    let mut key = active_color_key(active_color) ^ castles_key(allowed_castles);
+
+    for position in board.occupied() {
+        if let Some(piece) = &board[&position] {
+            key ^= piece_key(piece, position.index());
+        }
+    }
+
+    key ^ en_passant_key(board, active_color, en_passant_target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_distinct() {
+        let mut seen = std::collections::HashSet::new();
+
+        for color in KEYS.pieces {
+            for piece_type in color {
+                for key in piece_type {
+                    assert!(seen.insert(key));
+                }
+            }
+        }
+        for key in KEYS.castles.iter().flatten().chain(KEYS.en_passant.iter()) {
+            assert!(seen.insert(*key));
+        }
+        assert!(seen.insert(KEYS.black_to_move));
+    }
+
+    #[test]
+    fn test_piece_keys() {
+        let white_pawn = Piece::new(Color::White, PieceType::Pawn);
+        let black_pawn = Piece::new(Color::Black, PieceType::Pawn);
+
+        assert_ne!(piece_key(&white_pawn, 12), piece_key(&black_pawn, 12));
+        assert_ne!(piece_key(&white_pawn, 12), piece_key(&white_pawn, 13));
+    }
+}
@@ -41,7 +41,7 @@ async fn auth_endpoint_post(
     let mut auth = state.lock().await;
 
     match auth.add_user(&body.name) {
-        Ok(token) => ResponseCase::Success(AuthTokenInfo{token: token}),
+        Ok(token) => ResponseCase::Success(AuthTokenInfo{token}),
         Err(err) => error_response(422, err)
     }
 }
@@ -4,6 +4,11 @@ mod auth;
 //mod lobby;
 //mod game;
 
+//  Rocket's route codegen re-exports a URI macro for each handler, which nothing here
+//  uses yet.
+#[allow(unused_imports)]
+use auth::{rocket_uri_macro_auth_endpoint_cors, rocket_uri_macro_auth_endpoint_get, rocket_uri_macro_auth_endpoint_post};
+
 pub fn stage() -> AdHocFairing {
     AdHocFairing::on_ignite("endpoints", |rocket| async {
         rocket
@@ -29,7 +29,7 @@ impl<'r, T: Serialize + Clone> Responder<'r, 'r> for ResponseCase<T> {
                 Some(status) => {
                     let mut resp = Json(err.clone()).respond_to(req)?;
                     resp.set_status(status);
-                    return Ok(resp);
+                    Ok(resp)
                 },
                 _ => Err(Status::InternalServerError)
             }
@@ -38,10 +38,7 @@ fn parse_authz_header(text: &str) -> Option<String> {
         _ => return None,
     };
 
-    match capture.get(1) {
-        Some(t) => Some(t.as_str().to_string()),
-        _ => None
-    }
+    capture.get(1).map(|t| t.as_str().to_string())
 }
 
 impl AuthRegistry {
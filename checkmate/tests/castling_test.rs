@@ -0,0 +1,99 @@
+use checkmate::model::{State, StateBuilder, Color, Piece, PieceType, Position, CastleMoves};
+use checkmate::formats::{ToFEN, ToState, ToAlg, ToPosition};
+
+fn state_of(fen: &str) -> State {
+    fen.to_fen().to_state().unwrap()
+}
+
+fn castles_of(state: &State) -> Vec<CastleMoves> {
+    state.get_legal_moves().iter().filter_map(|m| m.castle_side()).collect()
+}
+
+fn play(state: State, from: &str, to: &str) -> State {
+    let (from, to) = (from.to_alg().to_position().unwrap(), to.to_alg().to_position().unwrap());
+    let next_move = state.get_legal_moves_from(&from).into_iter().find(|m| m.to == to).unwrap();
+
+    state.next_for_move(&next_move)
+}
+
+#[test]
+fn castles_both_sides() {
+    let state = state_of("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+    assert_eq!(castles_of(&state), vec![CastleMoves::KingSide, CastleMoves::QueenSide]);
+
+    let castled = play(state, "e1", "g1");
+    assert!(castled.to_fen().to_string().starts_with("r3k2r/8/8/8/8/8/8/R4RK1 b kq - "));
+}
+
+#[test]
+fn castle_requires_empty_path() {
+    //  The b1 knight blocks the queenside rook's path though not the king's.
+    assert_eq!(castles_of(&state_of("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1")), vec![CastleMoves::KingSide]);
+    assert_eq!(castles_of(&state_of("r3k2r/8/8/8/8/8/8/R3KB1R w KQkq - 0 1")), vec![CastleMoves::QueenSide]);
+}
+
+#[test]
+fn castle_requires_rook_on_start_square() {
+    //  "K" names the outermost kingside rook, and there is none.
+    assert!("r3k2r/8/8/8/8/8/8/1R2K3 w KQkq - 0 1".to_fen().to_state().is_err());
+
+    //  Rights that don't match the board are only reachable through the unchecked builder.
+    let mut builder = StateBuilder::new();
+    for (piece_type, position) in [(PieceType::King, (0, 4)), (PieceType::Rook, (0, 1)), (PieceType::Rook, (0, 6))] {
+        builder.board_builder().place_piece(Piece::new(Color::White, piece_type), Position::new(position.0, position.1));
+    }
+    builder.board_builder().place_piece(Piece::new(Color::Black, PieceType::King), Position::new(7, 4));
+    builder.set_abstract_history(Color::White, [CastleMoves::all(), CastleMoves::none()], None);
+
+    assert!(castles_of(&builder.build()).is_empty());
+}
+
+#[test]
+fn castle_out_of_or_through_check() {
+    //  In check from e4.
+    assert!(castles_of(&state_of("r3k2r/8/8/8/4r3/8/8/R3K2R w KQkq - 0 1")).is_empty());
+    //  f1 and c1 are attacked, b1 may be.
+    assert_eq!(castles_of(&state_of("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1")), vec![CastleMoves::QueenSide]);
+    assert_eq!(castles_of(&state_of("r3k2r/8/8/8/8/8/2r5/R3K2R w KQkq - 0 1")), vec![CastleMoves::KingSide]);
+    assert_eq!(castles_of(&state_of("r3k2r/8/8/8/8/8/1r6/R3K2R w KQkq - 0 1")).len(), 2);
+}
+
+#[test]
+fn king_move_forfeits_castles() {
+    let mut state = state_of("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+    state = play(state, "e1", "e2");
+    state = play(state, "e8", "d8");
+    state = play(state, "e2", "e1");
+    state = play(state, "d8", "e8");
+
+    assert_eq!(state.get_allowed_castles(Color::White), CastleMoves::none());
+    assert_eq!(state.get_allowed_castles(Color::Black), CastleMoves::none());
+    assert!(castles_of(&state).is_empty());
+    assert!(state.to_fen().to_string().contains(" w - - "));
+}
+
+#[test]
+fn rook_move_forfeits_one_side() {
+    let mut state = state_of("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+    state = play(state, "h1", "h2");
+    state = play(state, "a8", "b8");
+    state = play(state, "h2", "h1");
+    state = play(state, "b8", "a8");
+
+    assert_eq!(state.get_allowed_castles(Color::White), CastleMoves::QueenSide);
+    assert_eq!(state.get_allowed_castles(Color::Black), CastleMoves::KingSide);
+    assert_eq!(castles_of(&state), vec![CastleMoves::QueenSide]);
+}
+
+#[test]
+fn rook_capture_forfeits_opponent_side() {
+    let mut state = state_of("r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 0 1");
+
+    state = play(state, "g2", "h1");
+
+    assert_eq!(state.get_allowed_castles(Color::White), CastleMoves::QueenSide);
+    assert!(state.board[&Position::new(0, 7)].is_some());
+}
@@ -0,0 +1,68 @@
+use checkmate::model::{State, Color, CastleMoves, Position};
+use checkmate::formats::{ToFEN, ToShredderFEN, ToState, ToAlg, ToMove, ToSAN};
+
+fn state_of(fen: &str) -> State {
+    fen.to_fen().to_state().unwrap()
+}
+
+fn assert_perft(fen: &str, expected: &[u64]) {
+    let state = state_of(fen);
+
+    for (depth, count) in expected.iter().enumerate() {
+        assert_eq!(state.perft(depth as u32 + 1), *count, "{} at depth {}", fen, depth + 1);
+    }
+}
+
+#[test]
+fn perft_chess960() {
+    assert_perft("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", &[21, 528, 12189, 326672]);
+    assert_perft("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", &[21, 807, 18002, 667366]);
+    assert_perft("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", &[20, 479, 10471, 273318]);
+}
+
+#[test]
+fn king_step_onto_castle_square() {
+    //  Both the king step and O-O put the king on g1.
+    let state = state_of("r4k1r/8/8/8/8/8/8/R4K1R w KQkq - 0 1");
+
+    let step = "Kg1".to_alg().to_move(&state).unwrap();
+    assert!(step.castle.is_none());
+    assert_eq!(step.to_san(&state).to_string(), "Kg1");
+
+    let castle = "O-O".to_alg().to_move(&state).unwrap();
+    assert_eq!(castle.castle, Some((Position::new(0, 7), Position::new(0, 5))));
+    assert_eq!(castle.to_san(&state).to_string(), "O-O+");
+}
+
+#[test]
+fn castle_without_moving_king() {
+    let state = state_of("r5kr/8/8/8/8/8/8/R5KR w KQkq - 0 1");
+    let castle = "O-O".to_alg().to_move(&state).unwrap();
+
+    assert_eq!((&castle.from, &castle.to), (&Position::new(0, 6), &Position::new(0, 6)));
+    assert_eq!(castle.to_san(&state).to_string(), "O-O");
+
+    let castled = state.next_for_move(&castle);
+    assert_eq!(castled.to_fen().to_string(), "r5kr/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    assert_eq!(castled.to_shredder_fen().to_string(), "r5kr/8/8/8/8/8/8/R4RK1 b ha - 1 1");
+}
+
+#[test]
+fn castle_rook_shields_king_path() {
+    //  The b1 rook blocks the a1 queen until it castles to d1, leaving c1 attacked.
+    let state = state_of("4k3/8/8/8/8/8/8/qR1K4 w B - 0 1");
+
+    assert!(state.get_legal_moves().iter().all(|m| m.castle.is_none()));
+}
+
+#[test]
+fn inner_rook_move_forfeits_its_side() {
+    let mut state = state_of("r3k3/8/8/8/8/8/8/RR2K3 w Bq - 0 1");
+
+    state = state.next_for_move(&"Ra2".to_alg().to_move(&state).unwrap());
+    state = state.next_for_move(&"Kd8".to_alg().to_move(&state).unwrap());
+    assert_eq!(state.get_allowed_castles(Color::White), CastleMoves::QueenSide);
+
+    state = state.next_for_move(&"Rbb2".to_alg().to_move(&state).unwrap());
+    assert_eq!(state.get_allowed_castles(Color::White), CastleMoves::none());
+}
@@ -0,0 +1,90 @@
+use checkmate::model::State;
+use checkmate::formats::{ToFEN, ToState};
+
+//  Node counts are the published results for each position; the edge case counts are
+//  taken at reduced depth from positions whose full-depth counts match the published ones.
+
+fn perft_of(fen: &str, depth: u32) -> u64 {
+    fen.to_fen().to_state().unwrap().perft(depth)
+}
+
+fn assert_perft(fen: &str, expected: &[u64]) {
+    let state = fen.to_fen().to_state().unwrap();
+
+    for (depth, count) in expected.iter().enumerate() {
+        assert_eq!(state.perft(depth as u32 + 1), *count, "{} at depth {}", fen, depth + 1);
+    }
+}
+
+#[test]
+fn perft_initial() {
+    assert_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn perft_kiwipete() {
+    assert_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]);
+}
+
+#[test]
+fn perft_position_3() {
+    assert_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]);
+}
+
+#[test]
+fn perft_position_4() {
+    assert_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]);
+}
+
+#[test]
+fn perft_position_5() {
+    assert_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]);
+}
+
+#[test]
+fn perft_illegal_en_passant() {
+    assert_eq!(perft_of("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 4), 10138);
+    assert_eq!(perft_of("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 4), 10276);
+}
+
+#[test]
+fn perft_en_passant_gives_check() {
+    assert_eq!(perft_of("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 4), 13931);
+}
+
+#[test]
+fn perft_castling() {
+    assert_eq!(perft_of("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 4), 6399);
+    assert_eq!(perft_of("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 4), 7418);
+    assert_eq!(perft_of("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 3), 27826);
+    assert_eq!(perft_of("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 3), 50509);
+}
+
+#[test]
+fn perft_promotions() {
+    assert_eq!(perft_of("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 4), 19174);
+    assert_eq!(perft_of("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 5), 38983);
+    assert_eq!(perft_of("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 5), 18135);
+}
+
+#[test]
+fn perft_discovered_check() {
+    assert_eq!(perft_of("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 4), 31961);
+}
+
+#[test]
+fn perft_stalemate_and_checkmate() {
+    assert_eq!(perft_of("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6), 2217);
+    assert_eq!(perft_of("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 5), 10857);
+    assert_eq!(perft_of("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 3), 6559);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let state = State::default();
+
+    let divided = state.divide(3);
+    assert_eq!(divided.len(), 20);
+    assert_eq!(divided.iter().map(|(_, count)| count).sum::<u64>(), state.perft(3));
+    assert!(divided.iter().all(|(_, count)| *count > 0));
+}